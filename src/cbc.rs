@@ -0,0 +1,256 @@
+use crate::{
+    encrypt::{blocks_to_bytes, bytes_to_blocks, BlockCipher},
+    error::DesError,
+};
+
+pub fn cbc_encrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: u64,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    let plaintext_blocks = bytes_to_blocks(plaintext)?;
+    let mut ciphertext_blocks: Vec<u64> = Vec::with_capacity(plaintext_blocks.len());
+    let mut prev_block = iv;
+    for plaintext_block in plaintext_blocks {
+        prev_block = cipher.encrypt_block(plaintext_block ^ prev_block);
+        ciphertext_blocks.push(prev_block);
+    }
+    return Ok(blocks_to_bytes(&ciphertext_blocks));
+}
+
+pub fn cbc_decrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: u64,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    let ciphertext_blocks = bytes_to_blocks(ciphertext)?;
+    let mut plaintext_blocks: Vec<u64> = Vec::with_capacity(ciphertext_blocks.len());
+    let mut prev_block = iv;
+    for ciphertext_block in ciphertext_blocks {
+        plaintext_blocks.push(cipher.decrypt_block(ciphertext_block) ^ prev_block);
+        prev_block = ciphertext_block;
+    }
+    return Ok(blocks_to_bytes(&plaintext_blocks));
+}
+
+/// Ciphertext stealing variants from the NIST SP 800-38A addendum. They only differ in where
+/// the partial penultimate ciphertext block ends up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CiphertextStealing {
+    /// Partial block stays in place: `.., C(n-1)*, Cn`.
+    Cs1,
+    /// Last two blocks swapped only when the input is not block aligned.
+    Cs2,
+    /// Last two blocks always swapped (Kerberos 5).
+    Cs3,
+}
+
+fn swaps_last_blocks(variant: CiphertextStealing, partial_length: usize) -> bool {
+    return match variant {
+        CiphertextStealing::Cs1 => false,
+        CiphertextStealing::Cs2 => partial_length != 8,
+        CiphertextStealing::Cs3 => true,
+    };
+}
+
+/// CBC that keeps the output the same length as the input, which must be at least one block.
+pub fn cbc_cs_encrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: u64,
+    variant: CiphertextStealing,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    if plaintext.len() < 8 {
+        return Err(DesError::InvalidBlockLength {
+            expected: 8,
+            actual: plaintext.len(),
+        });
+    }
+    // zero-fill the last block, run plain CBC, then drop the bytes the last block stole
+    let partial_length = (plaintext.len() - 1) % 8 + 1;
+    let mut padded_plaintext = plaintext.to_vec();
+    padded_plaintext.resize(plaintext.len() + 8 - partial_length, 0);
+    let mut ciphertext = cbc_encrypt(cipher, iv, &padded_plaintext)?;
+    if ciphertext.len() == 8 {
+        return Ok(ciphertext);
+    }
+    let last_block = ciphertext.split_off(ciphertext.len() - 8);
+    ciphertext.truncate(ciphertext.len() - 8 + partial_length);
+    if swaps_last_blocks(variant, partial_length) {
+        let partial_block = ciphertext.split_off(ciphertext.len() - partial_length);
+        ciphertext.extend_from_slice(&last_block);
+        ciphertext.extend_from_slice(&partial_block);
+    } else {
+        ciphertext.extend_from_slice(&last_block);
+    }
+    return Ok(ciphertext);
+}
+
+pub fn cbc_cs_decrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: u64,
+    variant: CiphertextStealing,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    if ciphertext.len() < 8 {
+        return Err(DesError::InvalidBlockLength {
+            expected: 8,
+            actual: ciphertext.len(),
+        });
+    }
+    if ciphertext.len() == 8 {
+        return cbc_decrypt(cipher, iv, ciphertext);
+    }
+    let partial_length = (ciphertext.len() - 1) % 8 + 1;
+    let tail_start = ciphertext.len() - 8 - partial_length;
+    let (head, tail) = ciphertext.split_at(tail_start);
+    // bring the tail back into CS1 order: partial penultimate block, then the full last block
+    let (partial_block, last_block) = if swaps_last_blocks(variant, partial_length) {
+        (&tail[8..], &tail[..8])
+    } else {
+        (&tail[..partial_length], &tail[partial_length..])
+    };
+
+    let last_block = bytes_to_blocks(last_block)?[0];
+    let decrypted_last_block = cipher.decrypt_block(last_block).to_be_bytes();
+    // the stolen bytes of the penultimate ciphertext block come back out of the last block
+    let mut penultimate_block = partial_block.to_vec();
+    penultimate_block.extend_from_slice(&decrypted_last_block[partial_length..]);
+    let last_plaintext: Vec<u8> = decrypted_last_block[..partial_length]
+        .iter()
+        .zip(partial_block)
+        .map(|(decrypted_byte, ciphertext_byte)| decrypted_byte ^ ciphertext_byte)
+        .collect();
+
+    let mut cbc_ciphertext = head.to_vec();
+    cbc_ciphertext.extend_from_slice(&penultimate_block);
+    let mut plaintext = cbc_decrypt(cipher, iv, &cbc_ciphertext)?;
+    plaintext.extend_from_slice(&last_plaintext);
+    return Ok(plaintext);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbc::{
+        cbc_cs_decrypt, cbc_cs_encrypt, cbc_decrypt, cbc_encrypt, CiphertextStealing,
+    };
+    use crate::encrypt::Des;
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+
+    // FIPS 81 Appendix C, "Now is the time for all "
+    const FIPS_81_KEY: u64 = 0x0123456789abcdef;
+    const FIPS_81_IV: u64 = 0x1234567890abcdef;
+    const FIPS_81_PLAINTEXT: &str = "4e6f77206973207468652074696d6520666f7220616c6c20";
+    const FIPS_81_CIPHERTEXT: &str = "e5c7cdde872bf27c43e934008c389c0f683788499a7c05f6";
+
+    #[test]
+    fn cbc_encrypt_fips_81_vector() {
+        let des = Des::new(FIPS_81_KEY);
+        let ciphertext =
+            cbc_encrypt(&des, FIPS_81_IV, &decode(FIPS_81_PLAINTEXT).unwrap()).unwrap();
+        assert_eq!(encode(&ciphertext), FIPS_81_CIPHERTEXT);
+    }
+
+    #[test]
+    fn cbc_decrypt_fips_81_vector() {
+        let des = Des::new(FIPS_81_KEY);
+        let plaintext =
+            cbc_decrypt(&des, FIPS_81_IV, &decode(FIPS_81_CIPHERTEXT).unwrap()).unwrap();
+        assert_eq!(plaintext, b"Now is the time for all ");
+    }
+
+    #[test]
+    fn cbc_hides_repeated_plaintext_blocks() {
+        let des = Des::new(FIPS_81_KEY);
+        let ciphertext = cbc_encrypt(&des, FIPS_81_IV, &[0x41; 16]).unwrap();
+        assert_ne!(ciphertext[0..8], ciphertext[8..16]);
+        assert_eq!(
+            cbc_decrypt(&des, FIPS_81_IV, &ciphertext).unwrap(),
+            [0x41; 16]
+        );
+    }
+
+    #[test]
+    fn cbc_rejects_partial_block() {
+        let des = Des::new(FIPS_81_KEY);
+        assert_eq!(
+            cbc_encrypt(&des, FIPS_81_IV, &[0; 12]),
+            Err(DesError::InvalidBlockLength {
+                expected: 8,
+                actual: 12
+            })
+        );
+    }
+
+    #[test]
+    fn cbc_cs1_steals_from_the_penultimate_block() {
+        let des = Des::new(FIPS_81_KEY);
+        let plaintext = b"Now is the t";
+        let c1 = des.encrypt_block(u64::from_be_bytes(*b"Now is t") ^ FIPS_81_IV);
+        let c2 = des.encrypt_block(u64::from_be_bytes(*b"he t\0\0\0\0") ^ c1);
+        let mut expected = c1.to_be_bytes()[..4].to_vec();
+        expected.extend_from_slice(&c2.to_be_bytes());
+
+        let ciphertext =
+            cbc_cs_encrypt(&des, FIPS_81_IV, CiphertextStealing::Cs1, plaintext).unwrap();
+        assert_eq!(ciphertext, expected);
+    }
+
+    #[test]
+    fn cbc_cs_variants_differ_only_in_block_order() {
+        let des = Des::new(FIPS_81_KEY);
+        let plaintext = decode(FIPS_81_PLAINTEXT).unwrap();
+        let cs1 =
+            cbc_cs_encrypt(&des, FIPS_81_IV, CiphertextStealing::Cs1, &plaintext[..21]).unwrap();
+        let cs2 =
+            cbc_cs_encrypt(&des, FIPS_81_IV, CiphertextStealing::Cs2, &plaintext[..21]).unwrap();
+        let cs3 =
+            cbc_cs_encrypt(&des, FIPS_81_IV, CiphertextStealing::Cs3, &plaintext[..21]).unwrap();
+        assert_eq!(cs2, cs3);
+        assert_eq!(cs1[..8], cs2[..8]);
+        assert_eq!(cs1[8..13], cs2[16..]);
+        assert_eq!(cs1[13..], cs2[8..16]);
+
+        // block aligned input: CS1 and CS2 are plain CBC, CS3 still swaps
+        let cbc = cbc_encrypt(&des, FIPS_81_IV, &plaintext).unwrap();
+        let cs1 = cbc_cs_encrypt(&des, FIPS_81_IV, CiphertextStealing::Cs1, &plaintext).unwrap();
+        let cs2 = cbc_cs_encrypt(&des, FIPS_81_IV, CiphertextStealing::Cs2, &plaintext).unwrap();
+        let cs3 = cbc_cs_encrypt(&des, FIPS_81_IV, CiphertextStealing::Cs3, &plaintext).unwrap();
+        assert_eq!(encode(&cs1), FIPS_81_CIPHERTEXT);
+        assert_eq!(cs2, cbc);
+        assert_eq!(cs3[16..], cbc[8..16]);
+        assert_eq!(cs3[8..16], cbc[16..]);
+    }
+
+    #[test]
+    fn cbc_cs_round_trips_lengths_9_to_64() {
+        let des = Des::new(FIPS_81_KEY);
+        let message: Vec<u8> = (0..64).map(|byte| byte as u8 ^ 0x5a).collect();
+        for variant in [
+            CiphertextStealing::Cs1,
+            CiphertextStealing::Cs2,
+            CiphertextStealing::Cs3,
+        ] {
+            for length in 9..=64 {
+                let plaintext = &message[..length];
+                let ciphertext = cbc_cs_encrypt(&des, FIPS_81_IV, variant, plaintext).unwrap();
+                assert_eq!(ciphertext.len(), length);
+                let decrypted = cbc_cs_decrypt(&des, FIPS_81_IV, variant, &ciphertext).unwrap();
+                assert_eq!(decrypted, plaintext, "{:?} length {}", variant, length);
+            }
+        }
+    }
+
+    #[test]
+    fn cbc_cs_rejects_less_than_one_block() {
+        let des = Des::new(FIPS_81_KEY);
+        assert_eq!(
+            cbc_cs_encrypt(&des, FIPS_81_IV, CiphertextStealing::Cs3, &[0; 7]),
+            Err(DesError::InvalidBlockLength {
+                expected: 8,
+                actual: 7
+            })
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use crate::encrypt::BlockCipher;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CfbSegment {
+    Bits1,
+    Bits8,
+    Bits64,
+}
+
+/// Cipher feedback stream state; data can be fed in pieces of any length.
+pub struct Cfb<'a, C: BlockCipher> {
+    cipher: &'a C,
+    segment: CfbSegment,
+    shift_register: u64,
+    // byte offset into the current keystream block, only used by 64-bit segments
+    position: usize,
+    keystream: u64,
+}
+
+impl<'a, C: BlockCipher> Cfb<'a, C> {
+    pub fn new(cipher: &'a C, iv: u64, segment: CfbSegment) -> Cfb<'a, C> {
+        return Cfb {
+            cipher,
+            segment,
+            shift_register: iv,
+            position: 0,
+            keystream: 0,
+        };
+    }
+
+    pub fn encrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            *byte = self.process_byte(*byte, false);
+        }
+    }
+
+    pub fn decrypt(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            *byte = self.process_byte(*byte, true);
+        }
+    }
+
+    fn process_byte(&mut self, input: u8, decrypting: bool) -> u8 {
+        return match self.segment {
+            CfbSegment::Bits1 => {
+                let mut output: u8 = 0;
+                for bit_index in (0..8).rev() {
+                    let input_bit = ((input >> bit_index) & 1) as u64;
+                    let output_bit =
+                        input_bit ^ (self.cipher.encrypt_block(self.shift_register) >> 63);
+                    let ciphertext_bit = if decrypting { input_bit } else { output_bit };
+                    self.shift_register = (self.shift_register << 1) | ciphertext_bit;
+                    output |= (output_bit as u8) << bit_index;
+                }
+                output
+            }
+            CfbSegment::Bits8 => {
+                let output = input ^ (self.cipher.encrypt_block(self.shift_register) >> 56) as u8;
+                let ciphertext_byte = if decrypting { input } else { output };
+                self.shift_register = (self.shift_register << 8) | ciphertext_byte as u64;
+                output
+            }
+            CfbSegment::Bits64 => {
+                if self.position == 0 {
+                    self.keystream = self.cipher.encrypt_block(self.shift_register);
+                }
+                let shift = 56 - 8 * self.position;
+                let output = input ^ (self.keystream >> shift) as u8;
+                let ciphertext_byte = if decrypting { input } else { output };
+                // the register fills up with ciphertext one byte at a time
+                self.shift_register &= !(0xff << shift);
+                self.shift_register |= (ciphertext_byte as u64) << shift;
+                self.position = (self.position + 1) % 8;
+                output
+            }
+        };
+    }
+}
+
+pub fn cfb_encrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: u64,
+    segment: CfbSegment,
+    plaintext: &[u8],
+) -> Vec<u8> {
+    let mut ciphertext = plaintext.to_vec();
+    Cfb::new(cipher, iv, segment).encrypt(&mut ciphertext);
+    return ciphertext;
+}
+
+pub fn cfb_decrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: u64,
+    segment: CfbSegment,
+    ciphertext: &[u8],
+) -> Vec<u8> {
+    let mut plaintext = ciphertext.to_vec();
+    Cfb::new(cipher, iv, segment).decrypt(&mut plaintext);
+    return plaintext;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cfb::{cfb_decrypt, cfb_encrypt, Cfb, CfbSegment};
+    use crate::encrypt::Des;
+    use crate::hex::encode;
+
+    // FIPS 81 Appendix D, "Now is the time for all "
+    const FIPS_81_KEY: u64 = 0x0123456789abcdef;
+    const FIPS_81_IV: u64 = 0x1234567890abcdef;
+    const FIPS_81_PLAINTEXT: &[u8; 24] = b"Now is the time for all ";
+
+    #[test]
+    fn cfb64_fips_81_vector() {
+        let des = Des::new(FIPS_81_KEY);
+        let ciphertext = cfb_encrypt(&des, FIPS_81_IV, CfbSegment::Bits64, FIPS_81_PLAINTEXT);
+        assert_eq!(
+            encode(&ciphertext),
+            "f3096249c7f46e51a69e839b1a92f78403467133898ea622"
+        );
+        assert_eq!(
+            cfb_decrypt(&des, FIPS_81_IV, CfbSegment::Bits64, &ciphertext),
+            FIPS_81_PLAINTEXT
+        );
+    }
+
+    #[test]
+    fn cfb8_fips_81_vector() {
+        let des = Des::new(FIPS_81_KEY);
+        let ciphertext = cfb_encrypt(&des, FIPS_81_IV, CfbSegment::Bits8, FIPS_81_PLAINTEXT);
+        assert_eq!(
+            encode(&ciphertext),
+            "f31fda07011462ee187f43d80a7cd9b5b0d290da6e5b9a87"
+        );
+        assert_eq!(
+            cfb_decrypt(&des, FIPS_81_IV, CfbSegment::Bits8, &ciphertext),
+            FIPS_81_PLAINTEXT
+        );
+    }
+
+    #[test]
+    fn cfb1_sp_800_20_variable_plaintext_kat() {
+        // key 0101010101010101, the IV runs through the basis vectors and the plaintext is a
+        // zero bit, so each ciphertext bit is the leftmost bit of the TECB variable plaintext
+        // KAT output for that IV
+        let des = Des::new(0x0101010101010101);
+        let expected_bits: [u8; 4] = [1, 1, 0, 0];
+        for (bit, expected_bit) in expected_bits.iter().enumerate() {
+            let iv: u64 = 0x8000000000000000 >> bit;
+            let ciphertext = cfb_encrypt(&des, iv, CfbSegment::Bits1, &[0]);
+            assert_eq!(ciphertext[0] >> 7, *expected_bit);
+        }
+    }
+
+    #[test]
+    fn cfb1_regression() {
+        // not a published vector: this only pins the output, which matches OpenSSL's des-cfb1
+        let des = Des::new(FIPS_81_KEY);
+        let ciphertext = cfb_encrypt(&des, FIPS_81_IV, CfbSegment::Bits1, FIPS_81_PLAINTEXT);
+        assert_eq!(
+            encode(&ciphertext),
+            "cd1ec959add480f11ee40c517f29fb52b282946f94765a13"
+        );
+        assert_eq!(
+            cfb_decrypt(&des, FIPS_81_IV, CfbSegment::Bits1, &ciphertext),
+            FIPS_81_PLAINTEXT
+        );
+    }
+
+    #[test]
+    fn cfb64_streams_partial_segments() {
+        let des = Des::new(FIPS_81_KEY);
+        let expected = cfb_encrypt(
+            &des,
+            FIPS_81_IV,
+            CfbSegment::Bits64,
+            &FIPS_81_PLAINTEXT[..19],
+        );
+        let mut streamed = FIPS_81_PLAINTEXT[..19].to_vec();
+        let mut cfb = Cfb::new(&des, FIPS_81_IV, CfbSegment::Bits64);
+        let (first, rest) = streamed.split_at_mut(5);
+        cfb.encrypt(first);
+        cfb.encrypt(rest);
+        assert_eq!(streamed, expected);
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{
+    desx::DesX,
+    encrypt::{BlockCipher, Des},
+    error::DesError,
+    hex,
+    tdes::TripleDes,
+};
+
+/// The block ciphers a mode can be run with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CipherKind {
+    /// Single DES, 8-byte key
+    Des,
+    /// Triple DES (EDE), 16 or 24-byte key
+    TripleDes,
+    /// DES-X, 24-byte key
+    DesX,
+}
+
+/// A block cipher chosen at run time, for tools that take the cipher as an option.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Cipher {
+    Des(Des),
+    TripleDes(Box<TripleDes>),
+    DesX(DesX),
+}
+
+impl Cipher {
+    /// Builds a `kind` cipher from its key bytes.
+    pub fn from_slice(kind: CipherKind, key: &[u8]) -> Result<Cipher, DesError> {
+        return match kind {
+            CipherKind::Des => Ok(Cipher::Des(Des::from_slice(key)?)),
+            CipherKind::TripleDes => Ok(Cipher::TripleDes(Box::new(TripleDes::from_slice(key)?))),
+            CipherKind::DesX => Ok(Cipher::DesX(DesX::from_slice(key)?)),
+        };
+    }
+
+    /// Builds a `kind` cipher from a key given in hex code.
+    pub fn from_hex_key(kind: CipherKind, key_input: &str) -> Result<Cipher, DesError> {
+        return Cipher::from_slice(kind, &hex::decode(key_input)?);
+    }
+}
+
+impl BlockCipher for Cipher {
+    fn encrypt_block(&self, block: u64) -> u64 {
+        return match self {
+            Cipher::Des(des) => des.encrypt_block(block),
+            Cipher::TripleDes(tdes) => tdes.encrypt_block(block),
+            Cipher::DesX(desx) => desx.encrypt_block(block),
+        };
+    }
+
+    fn decrypt_block(&self, block: u64) -> u64 {
+        return match self {
+            Cipher::Des(des) => des.decrypt_block(block),
+            Cipher::TripleDes(tdes) => tdes.decrypt_block(block),
+            Cipher::DesX(desx) => desx.decrypt_block(block),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cipher::{Cipher, CipherKind};
+    use crate::desx::DesX;
+    use crate::encrypt::{BlockCipher, Des};
+    use crate::error::DesError;
+    use crate::tdes::TripleDes;
+
+    #[test]
+    fn from_hex_key_builds_each_cipher() {
+        let des = Cipher::from_hex_key(CipherKind::Des, "133457799bbcdff1").unwrap();
+        assert_eq!(des, Cipher::Des(Des::new(0x133457799bbcdff1)));
+        assert_eq!(des.encrypt_block(0x0123456789abcdef), 0x85e813540f0ab405);
+        assert_eq!(des.decrypt_block(0x85e813540f0ab405), 0x0123456789abcdef);
+        assert_eq!(
+            Cipher::from_hex_key(CipherKind::TripleDes, "0123456789abcdef23456789abcdef01"),
+            Ok(Cipher::TripleDes(Box::new(TripleDes::new_ede2(
+                0x0123456789abcdef,
+                0x23456789abcdef01
+            ))))
+        );
+        assert_eq!(
+            Cipher::from_hex_key(
+                CipherKind::DesX,
+                "0123456789abcdef1011121314151617a0a1a2a3a4a5a6a7"
+            ),
+            Ok(Cipher::DesX(DesX::new(
+                0x0123456789abcdef,
+                0x1011121314151617,
+                0xa0a1a2a3a4a5a6a7
+            )))
+        );
+    }
+
+    #[test]
+    fn from_hex_key_rejects_bad_keys() {
+        assert_eq!(
+            Cipher::from_hex_key(CipherKind::Des, "133457799bbcdff1aa"),
+            Err(DesError::InvalidKeyLength {
+                expected: 8,
+                actual: 9
+            })
+        );
+        assert_eq!(
+            Cipher::from_hex_key(CipherKind::DesX, "133457799bbcdff"),
+            Err(DesError::InvalidHex("133457799bbcdff".to_string()))
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{ct::constant_time_eq, encrypt::BlockCipher, error::DesError};
+
+// reduction constant for 64-bit blocks, x^64 + x^4 + x^3 + x + 1
+const RB_64: u64 = 0x1B;
+/// Shorter tags are too easy to guess; this is the same floor as the ISO 9797-1 MACs.
+pub const MIN_TAG_LENGTH: usize = 4;
+
+fn double_block(block: u64) -> u64 {
+    let carry = block >> 63;
+    return (block << 1) ^ (carry * RB_64);
+}
+
+/// The two CMAC subkeys `K1` and `K2` derived from `L = E_K(0)`.
+pub fn get_cmac_subkeys<C: BlockCipher>(cipher: &C) -> (u64, u64) {
+    let l_block = cipher.encrypt_block(0);
+    let subkey_1 = double_block(l_block);
+    let subkey_2 = double_block(subkey_1);
+    return (subkey_1, subkey_2);
+}
+
+/// NIST SP 800-38B CMAC over DES or Triple DES, truncated to `tag_length` bytes (4 to 8).
+pub fn cmac<C: BlockCipher>(
+    cipher: &C,
+    data: &[u8],
+    tag_length: usize,
+) -> Result<Vec<u8>, DesError> {
+    if !(MIN_TAG_LENGTH..=8).contains(&tag_length) {
+        return Err(DesError::InvalidArgument(format!(
+            "CMAC tag length must be {} to 8 bytes, got {}",
+            MIN_TAG_LENGTH, tag_length
+        )));
+    }
+    let (subkey_1, subkey_2) = get_cmac_subkeys(cipher);
+    // an empty message is a single, completely padded block
+    let chunks: Vec<&[u8]> = match data.is_empty() {
+        true => vec![&[]],
+        false => data.chunks(8).collect(),
+    };
+    let block_count = chunks.len();
+    let mut chained_block: u64 = 0;
+    for (index, chunk) in chunks.into_iter().enumerate() {
+        let mut block_bytes: [u8; 8] = [0; 8];
+        block_bytes[..chunk.len()].copy_from_slice(chunk);
+        let mut block = u64::from_be_bytes(block_bytes);
+        if index == block_count - 1 {
+            if chunk.len() == 8 {
+                block ^= subkey_1;
+            } else {
+                block ^= (0x80 << (56 - 8 * chunk.len())) ^ subkey_2;
+            }
+        }
+        chained_block = cipher.encrypt_block(chained_block ^ block);
+    }
+    return Ok(chained_block.to_be_bytes()[..tag_length].to_vec());
+}
+
+/// Recomputes the `tag_length`-byte tag and compares it in constant time. The length is the
+/// verifier's choice, so a tag of any other length is rejected rather than checked as a
+/// shorter, easier to forge prefix.
+pub fn cmac_verify<C: BlockCipher>(
+    cipher: &C,
+    data: &[u8],
+    tag: &[u8],
+    tag_length: usize,
+) -> Result<bool, DesError> {
+    let expected_tag = cmac(cipher, data, tag_length)?;
+    return Ok(constant_time_eq(&expected_tag, tag));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cmac::{cmac, cmac_verify, get_cmac_subkeys};
+    use crate::encrypt::Des;
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::tdes::TripleDes;
+
+    const MESSAGES: [&str; 4] = [
+        "",
+        "6bc1bee22e409f96",
+        "6bc1bee22e409f96e93d7e117393172aae2d8a57",
+        "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51",
+    ];
+
+    #[test]
+    fn cmac_sp_800_38b_three_key_tdea() {
+        let tdes = TripleDes::new_ede3(0x8aa83bf8cbda1062, 0x0bc1bf19fbb6cd58, 0xbc313d4a371ca8b5);
+        let (subkey_1, subkey_2) = get_cmac_subkeys(&tdes);
+        assert_eq!(subkey_1, 0x9198e9d314e6535f);
+        assert_eq!(subkey_2, 0x2331d3a629cca6a5);
+        let expected_tags = [
+            "b7a688e122ffaf95",
+            "8e8f293136283797",
+            "743ddbe0ce2dc2ed",
+            "33e6b1092400eae5",
+        ];
+        for (message, expected_tag) in MESSAGES.iter().zip(expected_tags) {
+            let tag = cmac(&tdes, &decode(message).unwrap(), 8).unwrap();
+            assert_eq!(encode(&tag), expected_tag);
+        }
+    }
+
+    #[test]
+    fn cmac_sp_800_38b_two_key_tdea() {
+        let tdes = TripleDes::new_ede2(0x4cf15134a2850dd5, 0x8a3d10ba80570d38);
+        let expected_tags = [
+            "bd2ebf9a3ba00361",
+            "4ff2ab813c53ce83",
+            "62dd1b471902bd4e",
+            "31b1e431dabc4eb8",
+        ];
+        for (message, expected_tag) in MESSAGES.iter().zip(expected_tags) {
+            let tag = cmac(&tdes, &decode(message).unwrap(), 8).unwrap();
+            assert_eq!(encode(&tag), expected_tag);
+        }
+    }
+
+    #[test]
+    fn cmac_single_des() {
+        let des = Des::new(0x0123456789abcdef);
+        let expected_tags = [
+            "86f79c13fd306e67",
+            "e9dd5ef151147f9b",
+            "3e2f8310c569275e",
+            "9d1fc4d4c0259132",
+        ];
+        for (message, expected_tag) in MESSAGES.iter().zip(expected_tags) {
+            let tag = cmac(&des, &decode(message).unwrap(), 8).unwrap();
+            assert_eq!(encode(&tag), expected_tag);
+        }
+    }
+
+    #[test]
+    fn cmac_verify_accepts_truncated_tags_and_rejects_forgeries() {
+        let tdes = TripleDes::new_ede2(0x4cf15134a2850dd5, 0x8a3d10ba80570d38);
+        let message = decode(MESSAGES[2]).unwrap();
+        let tag = decode("62dd1b471902bd4e").unwrap();
+        assert_eq!(cmac_verify(&tdes, &message, &tag[..4], 4), Ok(true));
+        assert_eq!(cmac_verify(&tdes, &message, &tag, 8), Ok(true));
+        assert_eq!(
+            cmac_verify(&tdes, &message, &decode("62dd1b48").unwrap(), 4),
+            Ok(false)
+        );
+        assert_eq!(cmac_verify(&tdes, &message[1..], &tag, 8), Ok(false));
+        // a prefix of a valid tag is not a valid tag
+        assert_eq!(cmac_verify(&tdes, &message, &tag[..1], 8), Ok(false));
+        assert_eq!(cmac_verify(&tdes, &message, &tag[..4], 8), Ok(false));
+        assert_eq!(cmac_verify(&tdes, &message, &[], 8), Ok(false));
+        for tag_length in [0, 1, 3, 9] {
+            assert!(matches!(
+                cmac_verify(&tdes, &message, &tag, tag_length),
+                Err(DesError::InvalidArgument(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::{ct::constant_time_eq, encrypt::Des, error::DesError};
+
+/// The crypt(3) base-64 alphabet; note that it is not the RFC 4648 one.
+const CRYPT_ALPHABET: &[u8; 64] =
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const TRADITIONAL_ITERATIONS: u32 = 25;
+/// Iteration counts and salts of the BSDi format are 24-bit values.
+pub const BSDI_MAX_ITERATIONS: u32 = 0xffffff;
+
+fn ascii_to_bin(c: u8) -> Result<u32, DesError> {
+    return match CRYPT_ALPHABET.iter().position(|a| *a == c) {
+        Some(value) => Ok(value as u32),
+        None => Err(DesError::InvalidArgument(format!(
+            "invalid crypt salt character {:?}",
+            c as char
+        ))),
+    };
+}
+
+/// Four characters holding a 24-bit value, least significant 6 bits first.
+fn decode_24_bits(chars: &[u8]) -> Result<u32, DesError> {
+    let mut value = 0;
+    for (i, c) in chars.iter().enumerate() {
+        value |= ascii_to_bin(*c)? << (6 * i);
+    }
+    return Ok(value);
+}
+
+fn encode_24_bits(value: u32) -> String {
+    return (0..4)
+        .map(|i| CRYPT_ALPHABET[((value >> (6 * i)) & 0x3f) as usize] as char)
+        .collect();
+}
+
+/// The 64-bit result as 11 characters, 6 bits at a time from the most significant end,
+/// with the last character carrying 4 bits followed by 2 zero bits.
+fn encode_block(block: u64) -> String {
+    let bits = (block as u128) << 2;
+    return (0..11)
+        .map(|i| CRYPT_ALPHABET[((bits >> (60 - 6 * i)) & 0x3f) as usize] as char)
+        .collect();
+}
+
+/// Up to 8 password bytes shifted into the 7 key bits of each DES key byte.
+fn password_key(password: &[u8]) -> [u8; 8] {
+    let mut key: [u8; 8] = [0; 8];
+    for (key_byte, password_byte) in key.iter_mut().zip(password) {
+        *key_byte = password_byte << 1;
+    }
+    return key;
+}
+
+/// Encrypts the zero block `iterations` times with the salted E expansion.
+fn crypt_block(des: &Des, salt_bits: u32, iterations: u32) -> u64 {
+    let mut block = 0;
+    for _ in 0..iterations {
+        block = des.encrypt_block_salted(block, salt_bits);
+    }
+    return block;
+}
+
+/// Traditional Unix crypt(3): the first 8 password bytes form the key, the 12-bit salt
+/// perturbs the E expansion and the zero block is encrypted 25 times. Only the first two
+/// characters of `salt` are used, so an existing 13-character hash can be passed as is.
+pub fn crypt_des(password: &[u8], salt: &str) -> Result<String, DesError> {
+    let salt = salt.as_bytes();
+    if salt.len() < 2 {
+        return Err(DesError::InvalidArgument(
+            "crypt salt must be 2 characters".to_string(),
+        ));
+    }
+    let salt_bits = ascii_to_bin(salt[0])? | (ascii_to_bin(salt[1])? << 6);
+    let des = Des::from_bytes(password_key(password));
+    let block = crypt_block(&des, salt_bits, TRADITIONAL_ITERATIONS);
+    return Ok(format!(
+        "{}{}{}",
+        salt[0] as char,
+        salt[1] as char,
+        encode_block(block)
+    ));
+}
+
+/// The `_CCCCSSSS` setting of a BSDi extended hash: 24-bit iteration count, then salt.
+pub fn bsdi_setting(iterations: u32, salt: u32) -> Result<String, DesError> {
+    if !(1..=BSDI_MAX_ITERATIONS).contains(&iterations) || salt > 0xffffff {
+        return Err(DesError::InvalidArgument(format!(
+            "BSDi crypt needs 1 to {} iterations and a 24-bit salt",
+            BSDI_MAX_ITERATIONS
+        )));
+    }
+    return Ok(format!(
+        "_{}{}",
+        encode_24_bits(iterations),
+        encode_24_bits(salt)
+    ));
+}
+
+/// BSDi extended DES crypt. `setting` is `_`, 4 characters of iteration count and 4 of
+/// salt; an existing 20-character hash can be passed as is. Passwords longer than 8 bytes
+/// are folded in: the key encrypts itself and the next 8 bytes are XORed into the result.
+pub fn crypt_bsdi(password: &[u8], setting: &str) -> Result<String, DesError> {
+    let setting = setting.as_bytes();
+    if setting.len() < 9 || setting[0] != b'_' {
+        return Err(DesError::InvalidArgument(
+            "BSDi crypt setting must be '_' followed by 8 characters".to_string(),
+        ));
+    }
+    let iterations = decode_24_bits(&setting[1..5])?;
+    let salt_bits = decode_24_bits(&setting[5..9])?;
+    if iterations == 0 {
+        return Err(DesError::InvalidArgument(
+            "BSDi crypt iteration count must be at least 1".to_string(),
+        ));
+    }
+
+    let (first_chunk, rest) = password.split_at(password.len().min(8));
+    let mut key = u64::from_be_bytes(password_key(first_chunk));
+    let mut des = Des::new(key);
+    for chunk in rest.chunks(8) {
+        key = des.encrypt_block(key) ^ u64::from_be_bytes(password_key(chunk));
+        des = Des::new(key);
+    }
+    let block = crypt_block(&des, salt_bits, iterations);
+    return Ok(format!(
+        "{}{}",
+        String::from_utf8_lossy(&setting[..9]),
+        encode_block(block)
+    ));
+}
+
+/// Recomputes a traditional (13-character) or BSDi extended (20-character, `_` prefixed)
+/// hash with the salt stored in `hash` and compares in constant time.
+pub fn crypt_verify(password: &[u8], hash: &str) -> Result<bool, DesError> {
+    let computed_hash = match (hash.starts_with('_'), hash.len()) {
+        (true, 20) => crypt_bsdi(password, hash)?,
+        (false, 13) => crypt_des(password, hash)?,
+        _ => {
+            return Err(DesError::InvalidArgument(format!(
+                "crypt hash must be 13 characters, or 20 starting with '_', got {}",
+                hash.len()
+            )));
+        }
+    };
+    return Ok(constant_time_eq(computed_hash.as_bytes(), hash.as_bytes()));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypt::{bsdi_setting, crypt_bsdi, crypt_des, crypt_verify};
+    use crate::encrypt::{salt_expanded_block, Des};
+    use crate::error::DesError;
+
+    // checked against glibc (libxcrypt) crypt(3)
+    const TRADITIONAL_VECTORS: [(&str, &str); 6] = [
+        ("CCNf8Sbh3HDfQ", "U*U*U*U*"),
+        ("CCX.K.MFy4Ois", "U*U***U"),
+        ("CC4rMpbg9AMZ.", "U*U***U*"),
+        ("XXxzOu6maQKqQ", "*U*U*U*U"),
+        ("SDbsugeBiC58A", ""),
+        ("aaqPiZY5xR5l.", "test"),
+    ];
+
+    // FreeBSD libcrypt extended format, also checked against libxcrypt
+    const BSDI_VECTORS: [(&str, &str); 12] = [
+        ("_J9..CCCCXBrJUJV154M", "U*U*U*U*"),
+        ("_J9..CCCCXUhOBTXzaiE", "U*U***U"),
+        ("_J9..CCCC4gQ.mB/PffM", "U*U***U*"),
+        ("_J9..XXXXvlzQGqpPPdk", "*U*U*U*U"),
+        ("_J9..XXXXsqM/YSSP..Y", "*U*U*U*U*"),
+        ("_J9..XXXXVL7qJCnku0I", "*U*U*U*U*U*U*U*U"),
+        ("_J9..XXXXAj8cFbP5scI", "*U*U*U*U*U*U*U*U*"),
+        ("_J9..SDizh.vll5VED9g", "ab1234567"),
+        ("_J9..SDizRjWQ/zePPHc", "cr1234567"),
+        ("_J9..SDizxmRI1GjnQuE", "zxyDPWgydbQjgq"),
+        ("_K9..SaltNrQgIYUAeoY", "726 even"),
+        ("_J9..SDSD5YGyRCr4W4c", ""),
+    ];
+
+    #[test]
+    fn crypt_des_matches_glibc() {
+        for (hash, password) in TRADITIONAL_VECTORS {
+            assert_eq!(crypt_des(password.as_bytes(), &hash[..2]).unwrap(), hash);
+        }
+    }
+
+    #[test]
+    fn crypt_des_ignores_password_past_8_bytes() {
+        assert_eq!(
+            crypt_des(b"U*U*U*U*U*U*", "CC").unwrap(),
+            crypt_des(b"U*U*U*U*", "CC").unwrap()
+        );
+    }
+
+    #[test]
+    fn crypt_verify_checks_password() {
+        for (hash, password) in TRADITIONAL_VECTORS {
+            assert_eq!(crypt_verify(password.as_bytes(), hash), Ok(true));
+            assert_eq!(crypt_verify(b"wrong", hash), Ok(false));
+        }
+    }
+
+    #[test]
+    fn crypt_bsdi_matches_freebsd() {
+        for (hash, password) in BSDI_VECTORS {
+            assert_eq!(crypt_bsdi(password.as_bytes(), &hash[..9]).unwrap(), hash);
+            assert_eq!(crypt_verify(password.as_bytes(), hash), Ok(true));
+            assert_eq!(crypt_verify(b"wrong", hash), Ok(false));
+        }
+    }
+
+    #[test]
+    fn crypt_bsdi_folds_long_passwords() {
+        let setting = bsdi_setting(1, 0x7d731e).unwrap();
+        assert_eq!(setting, "_/...SALT");
+        assert_eq!(
+            crypt_bsdi(b"a much longer passphrase", &setting).unwrap(),
+            "_/...SALTDFDOQw7dI1w"
+        );
+        // unlike traditional crypt, the last byte still counts
+        assert_eq!(
+            crypt_bsdi(b"a much longer passphrasf", &setting).unwrap(),
+            "_/...SALT7FNQ83iApPU"
+        );
+        assert_eq!(bsdi_setting(725, 0).unwrap(), "_J9......");
+    }
+
+    #[test]
+    fn crypt_rejects_bad_salt_and_hash() {
+        assert!(matches!(
+            crypt_bsdi(b"test", "_J9..SDS"),
+            Err(DesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            crypt_bsdi(b"test", "_....SDSD"),
+            Err(DesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            bsdi_setting(0, 0),
+            Err(DesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            crypt_des(b"test", "a"),
+            Err(DesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            crypt_des(b"test", "a!"),
+            Err(DesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            crypt_verify(b"test", "aaqPiZY5xR5l"),
+            Err(DesError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn salt_swaps_expansion_halves() {
+        assert_eq!(salt_expanded_block(0xfff000_000000, 0), 0xfff000_000000);
+        assert_eq!(salt_expanded_block(0x800000_000000, 1), 0x000000_800000);
+        assert_eq!(salt_expanded_block(0xfff000_000000, 0xfff), 0x000000_fff000);
+        // a zero salt leaves DES unchanged
+        let des = Des::new(0x133457799bbcdff1);
+        assert_eq!(
+            des.encrypt_block_salted(0x0123456789abcdef, 0),
+            0x85e813540f0ab405
+        );
+    }
+}
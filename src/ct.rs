@@ -0,0 +1,30 @@
+use core::hint::black_box;
+
+/// Compares two byte strings in time that depends only on their lengths, not on where they
+/// differ. The accumulated difference goes through `black_box` so the optimiser cannot
+/// stop at the first differing byte.
+pub fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    let difference = left
+        .iter()
+        .zip(right)
+        .fold(0, |difference, (left_byte, right_byte)| {
+            black_box(difference | (left_byte ^ right_byte))
+        });
+    return black_box(difference) == 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ct::constant_time_eq;
+
+    #[test]
+    fn constant_time_eq_compares_contents_and_length() {
+        assert!(constant_time_eq(b"abcd", b"abcd"));
+        assert!(!constant_time_eq(b"abcd", b"abce"));
+        assert!(!constant_time_eq(b"abcd", b"abc"));
+        assert!(constant_time_eq(b"", b""));
+    }
+}
@@ -0,0 +1,375 @@
+use crate::{
+    encrypt::{BlockCipher, Des},
+    error::DesError,
+    tdes::TripleDes,
+};
+
+pub const KSN_LENGTH: usize = 10;
+/// The low 21 bits of the KSN count transactions.
+pub const COUNTER_MASK: u64 = 0x1fffff;
+/// X9.24-1 only uses counters with at most 10 one bits, about a million transactions.
+pub const MAX_COUNTER_ONE_BITS: u32 = 10;
+/// One future key register per counter bit.
+pub const FUTURE_KEY_REGISTERS: usize = 21;
+
+const KEY_REGISTER_MASK: u128 = 0xc0c0c0c0_00000000_c0c0c0c0_00000000;
+
+/// The derived keys a transaction key is XORed with before use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyVariant {
+    Pin,
+    MacRequest,
+    MacResponse,
+    DataRequest,
+    DataResponse,
+}
+
+impl KeyVariant {
+    fn mask(self) -> u128 {
+        return match self {
+            KeyVariant::Pin => 0x00000000_000000ff_00000000_000000ff,
+            KeyVariant::MacRequest => 0x00000000_0000ff00_00000000_0000ff00,
+            KeyVariant::MacResponse => 0x00000000_ff000000_00000000_ff000000,
+            KeyVariant::DataRequest => 0x00000000_00ff0000_00000000_00ff0000,
+            KeyVariant::DataResponse => 0x000000ff_00000000_000000ff_00000000,
+        };
+    }
+}
+
+fn split_key(key: u128) -> (u64, u64) {
+    return ((key >> 64) as u64, key as u64);
+}
+
+/// The rightmost 64 bits of the KSN, the part the key derivation works on.
+fn ksn_register(ksn: &[u8; KSN_LENGTH]) -> u64 {
+    return u64::from_be_bytes(ksn[2..].try_into().unwrap());
+}
+
+pub fn ksn_counter(ksn: &[u8; KSN_LENGTH]) -> u64 {
+    return ksn_register(ksn) & COUNTER_MASK;
+}
+
+/// The initial PIN encryption key (IPEK) a terminal is loaded with: the KSN with its
+/// counter cleared, encrypted under the BDK and under the BDK XOR `C0C0C0C000000000...`.
+pub fn initial_key(bdk: &[u8; 16], ksn: &[u8; KSN_LENGTH]) -> [u8; 16] {
+    let masked_bdk = (u128::from_be_bytes(*bdk) ^ KEY_REGISTER_MASK).to_be_bytes();
+    let initial_ksn = u64::from_be_bytes(ksn[..8].try_into().unwrap()) & !(COUNTER_MASK >> 16);
+    let key_left = TripleDes::from_double_length(bdk).encrypt_block(initial_ksn);
+    let key_right = TripleDes::from_double_length(&masked_bdk).encrypt_block(initial_ksn);
+    return ((u128::from(key_left) << 64) | u128::from(key_right)).to_be_bytes();
+}
+
+/// The non-reversible key generation process: each half of the new key is the KSN register
+/// encrypted with single DES, whitened with the right half of the (masked) current key.
+fn non_reversible_key(key: u128, ksn_register: u64) -> u128 {
+    let half = |key: u128| {
+        let (key_left, key_right) = split_key(key);
+        return Des::new(key_left).encrypt_block(ksn_register ^ key_right) ^ key_right;
+    };
+    let key_right = half(key);
+    let key_left = half(key ^ KEY_REGISTER_MASK);
+    return (u128::from(key_left) << 64) | u128::from(key_right);
+}
+
+/// Host side derivation of the transaction key for `ksn`, straight from the IPEK one counter
+/// bit at a time from the most significant end. [`DukptTerminal`] arrives at the same key.
+pub fn transaction_key(
+    initial_key: &[u8; 16],
+    ksn: &[u8; KSN_LENGTH],
+) -> Result<[u8; 16], DesError> {
+    let counter = ksn_counter(ksn);
+    if counter.count_ones() > MAX_COUNTER_ONE_BITS {
+        return Err(DesError::InvalidArgument(format!(
+            "KSN counter {:x} has more than {} one bits",
+            counter, MAX_COUNTER_ONE_BITS
+        )));
+    }
+    let mut ksn_register = ksn_register(ksn) & !COUNTER_MASK;
+    let mut key = u128::from_be_bytes(*initial_key);
+    let mut shift_register = 1 << 20;
+    while shift_register > 0 {
+        if counter & shift_register != 0 {
+            ksn_register |= shift_register;
+            key = non_reversible_key(key, ksn_register);
+        }
+        shift_register >>= 1;
+    }
+    return Ok(key.to_be_bytes());
+}
+
+/// Applies a variant to a transaction key. The data variants additionally encrypt each
+/// half under the variant key itself, so the data key cannot be turned back into the PIN key.
+pub fn variant_key(transaction_key: &[u8; 16], variant: KeyVariant) -> [u8; 16] {
+    let key = u128::from_be_bytes(*transaction_key) ^ variant.mask();
+    return match variant {
+        KeyVariant::DataRequest | KeyVariant::DataResponse => {
+            let (key_left, key_right) = split_key(key);
+            let tdes = TripleDes::from_double_length(&key.to_be_bytes());
+            let key_left = tdes.encrypt_block(key_left);
+            let key_right = tdes.encrypt_block(key_right);
+            ((u128::from(key_left) << 64) | u128::from(key_right)).to_be_bytes()
+        }
+        KeyVariant::Pin | KeyVariant::MacRequest | KeyVariant::MacResponse => key.to_be_bytes(),
+    };
+}
+
+/// The working key for one transaction straight from the BDK, as the host derives it.
+pub fn derive_key(
+    bdk: &[u8; 16],
+    ksn: &[u8; KSN_LENGTH],
+    variant: KeyVariant,
+) -> Result<[u8; 16], DesError> {
+    let transaction_key = transaction_key(&initial_key(bdk, ksn), ksn)?;
+    return Ok(variant_key(&transaction_key, variant));
+}
+
+/// The KSN of the next transaction: the counter moves to the next value with at most 10 one
+/// bits, the same sequence [`DukptTerminal`] steps through.
+pub fn next_ksn(ksn: &[u8; KSN_LENGTH]) -> Result<[u8; KSN_LENGTH], DesError> {
+    let mut counter = ksn_counter(ksn) + 1;
+    while counter.count_ones() > MAX_COUNTER_ONE_BITS {
+        // adding the lowest one bit clears the run of ones it starts
+        counter += counter & counter.wrapping_neg();
+    }
+    if counter > COUNTER_MASK {
+        return Err(DesError::InvalidArgument(
+            "KSN counter exhausted, the terminal needs a new initial key".to_string(),
+        ));
+    }
+    let ksn_register = (ksn_register(ksn) & !COUNTER_MASK) | counter;
+    let mut next_ksn = *ksn;
+    next_ksn[2..].copy_from_slice(&ksn_register.to_be_bytes());
+    return Ok(next_ksn);
+}
+
+/// Terminal side DUKPT (X9.24-1 Annex A). The terminal never holds the IPEK or a past key:
+/// register `n` holds the future key for the next counter whose lowest one bit is bit `n`.
+/// Each transaction takes the key for the current counter out of its register, derives the
+/// keys for the counters below it into the lower registers and moves the counter on.
+pub struct DukptTerminal {
+    future_keys: [Option<u128>; FUTURE_KEY_REGISTERS],
+    /// The KSN of the next transaction; a zero counter means the keys are used up.
+    ksn: [u8; KSN_LENGTH],
+}
+
+impl DukptTerminal {
+    /// Loads the IPEK: fills every future key register and discards the IPEK itself. The
+    /// first transaction uses counter 1.
+    pub fn load_initial_key(initial_key: &[u8; 16], ksn: &[u8; KSN_LENGTH]) -> DukptTerminal {
+        let initial_key = u128::from_be_bytes(*initial_key);
+        let ksn_base = ksn_register(ksn) & !COUNTER_MASK;
+        let mut future_keys: [Option<u128>; FUTURE_KEY_REGISTERS] = [None; FUTURE_KEY_REGISTERS];
+        for (bit, future_key) in future_keys.iter_mut().enumerate() {
+            *future_key = Some(non_reversible_key(initial_key, ksn_base | (1 << bit)));
+        }
+        let mut terminal = DukptTerminal {
+            future_keys,
+            ksn: *ksn,
+        };
+        terminal.set_counter(1);
+        return terminal;
+    }
+
+    /// The KSN the next transaction will send.
+    pub fn ksn(&self) -> [u8; KSN_LENGTH] {
+        return self.ksn;
+    }
+
+    fn set_counter(&mut self, counter: u64) {
+        let ksn_register = (ksn_register(&self.ksn) & !COUNTER_MASK) | counter;
+        self.ksn[2..].copy_from_slice(&ksn_register.to_be_bytes());
+    }
+
+    /// Runs one transaction: returns its KSN and the `variant` working key, erases the
+    /// transaction key and derives the future keys that follow it. Counters with 10 one bits
+    /// have no successors of their own, so the counter skips ahead past them.
+    pub fn next_transaction(
+        &mut self,
+        variant: KeyVariant,
+    ) -> Result<([u8; KSN_LENGTH], [u8; 16]), DesError> {
+        let counter = ksn_counter(&self.ksn);
+        if counter == 0 {
+            return Err(DesError::InvalidArgument(
+                "KSN counter exhausted, the terminal needs a new initial key".to_string(),
+            ));
+        }
+        let ksn = self.ksn;
+        let current_bit = counter.trailing_zeros() as usize;
+        let key = self.future_keys[current_bit].take().unwrap();
+        let next_counter = if counter.count_ones() < MAX_COUNTER_ONE_BITS {
+            let ksn_register = ksn_register(&ksn);
+            for bit in (0..current_bit).rev() {
+                self.future_keys[bit] = Some(non_reversible_key(key, ksn_register | (1 << bit)));
+            }
+            counter + 1
+        } else {
+            counter + (1 << current_bit)
+        };
+        if next_counter > COUNTER_MASK {
+            self.future_keys = [None; FUTURE_KEY_REGISTERS];
+            self.set_counter(0);
+        } else {
+            self.set_counter(next_counter);
+        }
+        return Ok((ksn, variant_key(&key.to_be_bytes(), variant)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dukpt::{
+        derive_key, initial_key, ksn_counter, next_ksn, transaction_key, variant_key,
+        DukptTerminal, KeyVariant, KSN_LENGTH,
+    };
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::pin::encrypt_pin_block;
+
+    // ANSI X9.24-1 Annex A
+    const BDK: &str = "0123456789abcdeffedcba9876543210";
+    const INITIAL_KSN: &str = "ffff9876543210e00000";
+    const IPEK: &str = "6ac292faa1315b4d858ab3a3d7d5933a";
+
+    fn bdk() -> [u8; 16] {
+        return decode(BDK).unwrap().try_into().unwrap();
+    }
+
+    fn ksn(ksn: &str) -> [u8; KSN_LENGTH] {
+        return decode(ksn).unwrap().try_into().unwrap();
+    }
+
+    #[test]
+    fn initial_key_x9_24_vector() {
+        assert_eq!(encode(&initial_key(&bdk(), &ksn(INITIAL_KSN))), IPEK);
+        // the counter does not take part
+        assert_eq!(
+            encode(&initial_key(&bdk(), &ksn("ffff9876543210e00001"))),
+            IPEK
+        );
+    }
+
+    #[test]
+    fn pin_keys_x9_24_vectors() {
+        for (ksn_input, pin_key) in [
+            ("ffff9876543210e00001", "042666b49184cf5c68de9628d0397b36"),
+            ("ffff9876543210e00002", "c46551cef9fd244faa9ad834130d3b38"),
+            ("ffff9876543210e00003", "0df3d9422aca561a47676d07ad6bad05"),
+            ("ffff9876543210e00010", "59598dcbd9bd943f94165ce453585fa8"),
+            ("ffff9876543210effc00", "f9430df97508246ec77be4ef4fdb9111"),
+        ] {
+            assert_eq!(
+                encode(&derive_key(&bdk(), &ksn(ksn_input), KeyVariant::Pin).unwrap()),
+                pin_key
+            );
+        }
+    }
+
+    #[test]
+    fn encrypted_pin_block_x9_24_vector() {
+        // PIN 1234, PAN 4012345678909
+        let pin_key = derive_key(&bdk(), &ksn("ffff9876543210e00001"), KeyVariant::Pin).unwrap();
+        assert_eq!(
+            encrypt_pin_block(&pin_key, 0x041274edcba9876f).unwrap(),
+            0x1b9c1845eb993a7a
+        );
+    }
+
+    #[test]
+    fn mac_and_data_variants() {
+        let ipek: [u8; 16] = decode(IPEK).unwrap().try_into().unwrap();
+        let key = transaction_key(&ipek, &ksn("ffff9876543210e00001")).unwrap();
+        assert_eq!(encode(&key), "042666b49184cfa368de9628d0397bc9");
+        for (variant, variant_key_output) in [
+            (KeyVariant::MacRequest, "042666b4918430a368de9628d03984c9"),
+            (KeyVariant::MacResponse, "042666b46e84cfa368de96282f397bc9"),
+            (KeyVariant::DataRequest, "448d3f076d8304036a55a3d7e0055a78"),
+            (KeyVariant::DataResponse, "ad7bfc8b06ad3a08a560b4105cf8d9e5"),
+        ] {
+            assert_eq!(encode(&variant_key(&key, variant)), variant_key_output);
+        }
+    }
+
+    #[test]
+    fn next_ksn_skips_counters_with_more_than_10_one_bits() {
+        assert_eq!(
+            encode(&next_ksn(&ksn(INITIAL_KSN)).unwrap()),
+            "ffff9876543210e00001"
+        );
+        // 0x3ff has 10 one bits, 0x400 is next
+        assert_eq!(
+            ksn_counter(&next_ksn(&ksn("ffff9876543210e003ff")).unwrap()),
+            0x400
+        );
+        assert_eq!(
+            ksn_counter(&next_ksn(&ksn("ffff9876543210e007fe")).unwrap()),
+            0x800
+        );
+        assert_eq!(
+            next_ksn(&ksn("ffff9876543210fff800")),
+            Err(DesError::InvalidArgument(
+                "KSN counter exhausted, the terminal needs a new initial key".to_string()
+            ))
+        );
+        assert!(matches!(
+            derive_key(&bdk(), &ksn("ffff9876543210e007ff"), KeyVariant::Pin),
+            Err(DesError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn terminal_pin_keys_x9_24_vectors() {
+        let ipek: [u8; 16] = decode(IPEK).unwrap().try_into().unwrap();
+        let mut terminal = DukptTerminal::load_initial_key(&ipek, &ksn(INITIAL_KSN));
+        let expected: [(u64, &str); 4] = [
+            (1, "042666b49184cf5c68de9628d0397b36"),
+            (2, "c46551cef9fd244faa9ad834130d3b38"),
+            (3, "0df3d9422aca561a47676d07ad6bad05"),
+            (0x10, "59598dcbd9bd943f94165ce453585fa8"),
+        ];
+        for (counter, pin_key) in expected {
+            while ksn_counter(&terminal.ksn()) < counter {
+                terminal.next_transaction(KeyVariant::Pin).unwrap();
+            }
+            let (transaction_ksn, key) = terminal.next_transaction(KeyVariant::Pin).unwrap();
+            assert_eq!(ksn_counter(&transaction_ksn), counter);
+            assert_eq!(encode(&key), pin_key);
+        }
+    }
+
+    #[test]
+    fn terminal_erases_used_keys() {
+        let ipek: [u8; 16] = decode(IPEK).unwrap().try_into().unwrap();
+        let mut terminal = DukptTerminal::load_initial_key(&ipek, &ksn(INITIAL_KSN));
+        assert!(terminal.future_keys.iter().all(Option::is_some));
+        // counter 1 has no successors of its own, counter 2 refills register 0
+        terminal.next_transaction(KeyVariant::Pin).unwrap();
+        assert_eq!(terminal.future_keys[0], None);
+        terminal.next_transaction(KeyVariant::Pin).unwrap();
+        assert_eq!(terminal.future_keys[1], None);
+        assert!(terminal.future_keys[0].is_some());
+    }
+
+    #[test]
+    fn terminal_follows_host_derivation_and_skips_counters() {
+        let ipek: [u8; 16] = decode(IPEK).unwrap().try_into().unwrap();
+        let mut terminal = DukptTerminal::load_initial_key(&ipek, &ksn(INITIAL_KSN));
+        let mut expected_ksn = next_ksn(&ksn(INITIAL_KSN)).unwrap();
+        // 0x7fe has 10 one bits, so 0x7ff is skipped
+        while ksn_counter(&expected_ksn) <= 0x800 {
+            let (transaction_ksn, key) = terminal.next_transaction(KeyVariant::Pin).unwrap();
+            assert_eq!(transaction_ksn, expected_ksn);
+            let counter = ksn_counter(&transaction_ksn);
+            if counter.is_power_of_two() || counter.is_multiple_of(61) || counter >= 0x7fe {
+                assert_eq!(
+                    key,
+                    variant_key(
+                        &transaction_key(&ipek, &transaction_ksn).unwrap(),
+                        KeyVariant::Pin
+                    )
+                );
+            }
+            expected_ksn = next_ksn(&expected_ksn).unwrap();
+        }
+        assert_eq!(ksn_counter(&terminal.ksn()), 0x801);
+    }
+}
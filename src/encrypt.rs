@@ -1,11 +1,14 @@
 use crate::{
     binary_pads,
+    error::DesError,
+    hex::check_hex,
     logging::{
         populate_inverse_ip_log_table, populate_ip_log_table, populate_kplus_log_table,
         populate_round_log_table, DesLog,
     },
     permutation_tables,
 };
+use std::fmt;
 use tabled::Table;
 
 use binary_pads::{
@@ -26,10 +29,10 @@ pub fn get_permutated_block<const N: usize>(
     right_shift_offset: u8,
 ) -> u64 {
     let mut permutated_block: u64 = 0;
-    for index in 0..N {
-        let target_bit_index: u8 = permutation_table[index] - 1;
+    for (index, position) in permutation_table.iter().enumerate() {
+        let target_bit_index: u8 = position - 1;
         let right_shift: u8 = 63 - target_bit_index;
-        let bit = (u64_block >> right_shift - right_shift_offset) & 1;
+        let bit = (u64_block >> (right_shift - right_shift_offset)) & 1;
         let new_block_with_bit: u64 = bit << (N - index - 1);
         permutated_block |= new_block_with_bit;
     }
@@ -99,8 +102,24 @@ pub fn get_s_box_index(bit_6_block: u64) -> usize {
     return (row * 16 + col) as usize;
 }
 
+/// Applies a crypt(3) salt to the 48-bit E expansion: every set bit `i` of the 24-bit
+/// `salt_bits` swaps expansion bits `i` and `i + 24`, counting from the first output bit.
+pub fn salt_expanded_block(expanded_block: u64, salt_bits: u32) -> u64 {
+    let swap_mask = ((salt_bits & 0xffffff).reverse_bits() >> 8) as u64;
+    let swapped_bits = ((expanded_block >> 24) ^ expanded_block) & swap_mask;
+    return expanded_block ^ swapped_bits ^ (swapped_bits << 24);
+}
+
 pub fn f_function(block_32: u64, key: u64) -> u64 {
-    let expanded_block = get_permutated_block(block_32, E_BIT_SELECTION_TABLE, 32);
+    return salted_f_function(block_32, key, 0);
+}
+
+/// The round function with the E expansion perturbed by a crypt(3) salt.
+pub fn salted_f_function(block_32: u64, key: u64, salt_bits: u32) -> u64 {
+    let expanded_block = salt_expanded_block(
+        get_permutated_block(block_32, E_BIT_SELECTION_TABLE, 32),
+        salt_bits,
+    );
     let key_xor_expanded_block = key ^ expanded_block;
     let b1 = (key_xor_expanded_block & FIRST_6BIT_IN_48) >> 42;
     let b1_sub = S1_TABLE[get_s_box_index(b1)];
@@ -132,101 +151,249 @@ pub fn f_function(block_32: u64, key: u64) -> u64 {
 
 fn run_16_rounds(
     plaintext_after_init_permutation_block: u64,
-    subkeys: [u64; 16],
-    des_log_table: &mut Vec<DesLog>,
+    subkeys: &[u64; 16],
+    salt_bits: u32,
+    mut des_log_table: Option<&mut Vec<DesLog>>,
 ) -> u64 {
     let (left_split, right_split) = split_permutated_key_64(plaintext_after_init_permutation_block);
     let mut prev_left_block = left_split;
     let mut prev_right_block = right_split;
     let mut left_block = 0;
     let mut right_block = 0;
-    for index in 0..16 {
+    for (index, subkey) in subkeys.iter().enumerate() {
         left_block = prev_right_block;
-        right_block = prev_left_block ^ f_function(prev_right_block, subkeys[index]);
+        right_block = prev_left_block ^ salted_f_function(prev_right_block, *subkey, salt_bits);
         prev_left_block = left_block;
         prev_right_block = right_block;
-        populate_round_log_table(
-            des_log_table,
-            index + 1,
-            subkeys[index],
-            left_block,
-            right_block,
-        );
+        if let Some(des_log_table) = des_log_table.as_deref_mut() {
+            populate_round_log_table(des_log_table, index + 1, *subkey, left_block, right_block);
+        }
     }
     return merge_32_block_in_reverse_order(left_block, right_block);
 }
 
-fn get_subkeys(key_input: String, des_log_table: &mut Vec<DesLog>) -> [u64; 16] {
-    let key_block: u64 = u64::from_str_radix(&key_input, 16).ok().unwrap();
+pub fn get_subkeys(key_block: u64) -> [u64; 16] {
     let permutated_key_block: u64 = get_permutated_block(key_block, PC_1_TABLE, 0);
-    populate_kplus_log_table(des_log_table, permutated_key_block);
     let (left, right) = split_permutated_key_56(permutated_key_block);
     let permuted_pc1_keys = get_pc1_shifted_keys(left, right);
     return get_pc2_permuted_keys(permuted_pc1_keys);
 }
 
-pub fn des_encrypt(plaintext_input: String, key_input: String) -> u64 {
+/// Single DES with the 16 round subkeys derived once at construction.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Des {
+    permutated_key_block: u64,
+    subkeys: [u64; 16],
+}
+
+impl Des {
+    pub fn new(key_block: u64) -> Des {
+        return Des {
+            permutated_key_block: get_permutated_block(key_block, PC_1_TABLE, 0),
+            subkeys: get_subkeys(key_block),
+        };
+    }
+
+    pub fn from_bytes(key: [u8; 8]) -> Des {
+        return Des::new(u64::from_be_bytes(key));
+    }
+
+    pub fn from_slice(key: &[u8]) -> Result<Des, DesError> {
+        let key: [u8; 8] = key.try_into().map_err(|_| DesError::InvalidKeyLength {
+            expected: 8,
+            actual: key.len(),
+        })?;
+        return Ok(Des::from_bytes(key));
+    }
+
+    pub fn subkeys(&self) -> [u64; 16] {
+        return self.subkeys;
+    }
+
+    pub fn encrypt_block(&self, block: u64) -> u64 {
+        return self.crypt_block(block, false, 0, None);
+    }
+
+    pub fn decrypt_block(&self, block: u64) -> u64 {
+        return self.crypt_block(block, true, 0, None);
+    }
+
+    /// Encryption with the salted E expansion crypt(3) uses; a zero salt is plain DES.
+    pub fn encrypt_block_salted(&self, block: u64, salt_bits: u32) -> u64 {
+        return self.crypt_block(block, false, salt_bits, None);
+    }
+
+    /// Same as `encrypt_block`, recording IP, K+, every round and IP-1 in `des_log_table`.
+    pub fn encrypt_block_traced(&self, block: u64, des_log_table: &mut Vec<DesLog>) -> u64 {
+        return self.crypt_block(block, false, 0, Some(des_log_table));
+    }
+
+    pub fn decrypt_block_traced(&self, block: u64, des_log_table: &mut Vec<DesLog>) -> u64 {
+        return self.crypt_block(block, true, 0, Some(des_log_table));
+    }
+
+    fn crypt_block(
+        &self,
+        block: u64,
+        reverse_subkeys: bool,
+        salt_bits: u32,
+        mut des_log_table: Option<&mut Vec<DesLog>>,
+    ) -> u64 {
+        let block_after_init_permutation: u64 =
+            get_permutated_block(block, INITIAL_PERMUTATION_TABLE, 0);
+        if let Some(des_log_table) = des_log_table.as_deref_mut() {
+            populate_ip_log_table(des_log_table, block_after_init_permutation);
+            populate_kplus_log_table(des_log_table, self.permutated_key_block);
+        }
+        let mut subkeys: [u64; 16] = self.subkeys;
+        if reverse_subkeys {
+            subkeys.reverse();
+        }
+        let reversed_block = run_16_rounds(
+            block_after_init_permutation,
+            &subkeys,
+            salt_bits,
+            des_log_table.as_deref_mut(),
+        );
+        let final_permutated_block: u64 =
+            get_permutated_block(reversed_block, INVERSE_PERMUTATION_TABLE, 0);
+        if let Some(des_log_table) = des_log_table {
+            populate_inverse_ip_log_table(des_log_table, final_permutated_block);
+        }
+        return final_permutated_block;
+    }
+}
+
+/// Shows the key check value instead of the key or subkeys.
+impl fmt::Debug for Des {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return f
+            .debug_struct("Des")
+            .field("kcv", &format_args!("{:06x}", self.encrypt_block(0) >> 40))
+            .finish_non_exhaustive();
+    }
+}
+
+/// A 64-bit block cipher that the chaining modes can be built on.
+pub trait BlockCipher {
+    fn encrypt_block(&self, block: u64) -> u64;
+    fn decrypt_block(&self, block: u64) -> u64;
+}
+
+impl BlockCipher for Des {
+    fn encrypt_block(&self, block: u64) -> u64 {
+        return Des::encrypt_block(self, block);
+    }
+
+    fn decrypt_block(&self, block: u64) -> u64 {
+        return Des::decrypt_block(self, block);
+    }
+}
+
+/// Splits `bytes` into big-endian 64-bit blocks; the length must be a multiple of 8.
+pub fn bytes_to_blocks(bytes: &[u8]) -> Result<Vec<u64>, DesError> {
+    if !bytes.len().is_multiple_of(8) {
+        return Err(DesError::InvalidBlockLength {
+            expected: 8,
+            actual: bytes.len(),
+        });
+    }
+    let blocks = bytes
+        .chunks_exact(8)
+        .map(|chunk| {
+            let mut block: [u8; 8] = [0; 8];
+            block.copy_from_slice(chunk);
+            u64::from_be_bytes(block)
+        })
+        .collect();
+    return Ok(blocks);
+}
+
+pub fn blocks_to_bytes(blocks: &[u64]) -> Vec<u8> {
+    return blocks
+        .iter()
+        .flat_map(|block| block.to_be_bytes())
+        .collect();
+}
+
+fn parse_hex_u64(input: &str) -> Result<u64, DesError> {
+    return u64::from_str_radix(input, 16).map_err(|_| DesError::InvalidHex(input.to_string()));
+}
+
+/// Parses a key given as exactly 16 hex digits.
+pub fn parse_hex_key(key_input: &str) -> Result<u64, DesError> {
+    check_hex(key_input)?;
+    if !key_input.len().is_multiple_of(2) {
+        return Err(DesError::InvalidHex(key_input.to_string()));
+    }
+    if key_input.len() != 16 {
+        return Err(DesError::InvalidKeyLength {
+            expected: 8,
+            actual: key_input.len() / 2,
+        });
+    }
+    return parse_hex_u64(key_input);
+}
+
+/// Parses a single 64-bit block given as exactly 16 hex digits.
+pub fn parse_hex_block(block_input: &str) -> Result<u64, DesError> {
+    check_hex(block_input)?;
+    if !block_input.len().is_multiple_of(2) {
+        return Err(DesError::InvalidHex(block_input.to_string()));
+    }
+    if block_input.len() != 16 {
+        return Err(DesError::InvalidBlockLength {
+            expected: 8,
+            actual: block_input.len() / 2,
+        });
+    }
+    return parse_hex_u64(block_input);
+}
+
+pub fn des_encrypt(plaintext_input: String, key_input: String) -> Result<u64, DesError> {
     let mut des_log_table: Vec<DesLog> = vec![];
-    let plaintext_u64_block = u64::from_str_radix(&plaintext_input, 16).ok().unwrap();
-    println!(
-        "+----- 🔐 ENCRYPTING: {} ------+",
-        format!("{:016x}", plaintext_u64_block)
-    );
+    let plaintext_u64_block = parse_hex_block(&plaintext_input)?;
+    let key_block: u64 = parse_hex_key(&key_input)?;
+    println!("+----- 🔐 ENCRYPTING: {:016x} ------+", plaintext_u64_block);
 
-    let plaintext_after_init_permutation_block =
-        get_permutated_block(plaintext_u64_block, INITIAL_PERMUTATION_TABLE, 0);
-    populate_ip_log_table(&mut des_log_table, plaintext_after_init_permutation_block);
-    let subkeys: [u64; 16] = get_subkeys(key_input, &mut des_log_table).clone();
-    let reversed_block = run_16_rounds(
-        plaintext_after_init_permutation_block,
-        subkeys,
-        &mut des_log_table,
-    );
-    let final_permutated_block = get_permutated_block(reversed_block, INVERSE_PERMUTATION_TABLE, 0);
-    populate_inverse_ip_log_table(&mut des_log_table, final_permutated_block);
+    let des = Des::new(key_block);
+    let final_permutated_block = des.encrypt_block_traced(plaintext_u64_block, &mut des_log_table);
     let table: String = Table::new(&des_log_table).to_string();
     println!("{}", table);
 
-    return final_permutated_block;
+    return Ok(final_permutated_block);
 }
 
-pub fn des_decrypt(ciphertext: String, key_input: String) -> u64 {
+pub fn des_decrypt(ciphertext: String, key_input: String) -> Result<u64, DesError> {
     let mut des_log_table: Vec<DesLog> = vec![];
-    let ciphertext_u64_block = u64::from_str_radix(&ciphertext, 16).ok().unwrap();
+    let ciphertext_u64_block = parse_hex_block(&ciphertext)?;
+    let key_block: u64 = parse_hex_key(&key_input)?;
     println!(
-        "+----- 🔓 DECRYPTING: {} ------+",
-        format!("{:016x}", ciphertext_u64_block)
-    );
-    let plaintext_after_init_permutation_block: u64 =
-        get_permutated_block(ciphertext_u64_block, INITIAL_PERMUTATION_TABLE, 0);
-    populate_ip_log_table(&mut des_log_table, plaintext_after_init_permutation_block);
-    let mut subkeys: [u64; 16] = get_subkeys(key_input, &mut des_log_table).clone();
-    subkeys.reverse();
-    let reversed_block = run_16_rounds(
-        plaintext_after_init_permutation_block,
-        subkeys,
-        &mut des_log_table,
+        "+----- 🔓 DECRYPTING: {:016x} ------+",
+        ciphertext_u64_block
     );
-    let final_permutated_block: u64 =
-        get_permutated_block(reversed_block, INVERSE_PERMUTATION_TABLE, 0);
-    populate_inverse_ip_log_table(&mut des_log_table, final_permutated_block);
+
+    let des = Des::new(key_block);
+    let final_permutated_block = des.decrypt_block_traced(ciphertext_u64_block, &mut des_log_table);
     let table: String = Table::new(&des_log_table).to_string();
     println!("{}", table);
 
-    return final_permutated_block;
+    return Ok(final_permutated_block);
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::encrypt::{des_decrypt, des_encrypt};
+    use crate::encrypt::{des_decrypt, des_encrypt, Des};
+    use crate::error::DesError;
 
     #[test]
     fn encrypt_02468aceeca86420_with_0f1571c947d9e859() {
         let ciphertext = des_encrypt(
             "0123456789ABCDEF".to_string(),
             "133457799BBCDFF1".to_string(),
-        );
-        let expected: u64 = u64::from_str_radix(&"85e813540f0ab405", 16).ok().unwrap();
+        )
+        .unwrap();
+        let expected: u64 = u64::from_str_radix("85e813540f0ab405", 16).ok().unwrap();
         assert_eq!(ciphertext, expected);
     }
 
@@ -235,8 +402,9 @@ mod tests {
         let ciphertext = des_encrypt(
             "02468aceeca86420".to_string(),
             "0f1571c947d9e859".to_string(),
-        );
-        let expected: u64 = u64::from_str_radix(&"da02ce3a89ecac3b", 16).ok().unwrap();
+        )
+        .unwrap();
+        let expected: u64 = u64::from_str_radix("da02ce3a89ecac3b", 16).ok().unwrap();
         assert_eq!(ciphertext, expected);
     }
 
@@ -245,13 +413,15 @@ mod tests {
         let ciphertext = des_encrypt(
             "0123456789ABCDEF".to_string(),
             "133457799BBCDFF1".to_string(),
-        );
+        )
+        .unwrap();
         let decrypted = des_decrypt(
             "85e813540f0ab405".to_string(),
             "133457799BBCDFF1".to_string(),
-        );
-        let expected_ciphertext: u64 = u64::from_str_radix(&"85e813540f0ab405", 16).ok().unwrap();
-        let expected_decrypted: u64 = u64::from_str_radix(&"0123456789ABCDEF", 16).ok().unwrap();
+        )
+        .unwrap();
+        let expected_ciphertext: u64 = u64::from_str_radix("85e813540f0ab405", 16).ok().unwrap();
+        let expected_decrypted: u64 = u64::from_str_radix("0123456789ABCDEF", 16).ok().unwrap();
         assert_eq!(ciphertext, expected_ciphertext);
         assert_eq!(decrypted, expected_decrypted);
     }
@@ -261,13 +431,15 @@ mod tests {
         let ciphertext = des_encrypt(
             "02468aceeca86420".to_string(),
             "0f1571c947d9e859".to_string(),
-        );
+        )
+        .unwrap();
         let decrypted = des_decrypt(
             "da02ce3a89ecac3b".to_string(),
             "0f1571c947d9e859".to_string(),
-        );
-        let expected_ciphertext: u64 = u64::from_str_radix(&"da02ce3a89ecac3b", 16).ok().unwrap();
-        let expected_decrypted: u64 = u64::from_str_radix(&"02468aceeca86420", 16).ok().unwrap();
+        )
+        .unwrap();
+        let expected_ciphertext: u64 = u64::from_str_radix("da02ce3a89ecac3b", 16).ok().unwrap();
+        let expected_decrypted: u64 = u64::from_str_radix("02468aceeca86420", 16).ok().unwrap();
         assert_eq!(ciphertext, expected_ciphertext);
         assert_eq!(decrypted, expected_decrypted);
     }
@@ -277,13 +449,15 @@ mod tests {
         let ciphertext = des_encrypt(
             "7772A5DC17CC382C".to_string(),
             "E31D1B22F059933E".to_string(),
-        );
+        )
+        .unwrap();
         let decrypted = des_decrypt(
             "7C7EE7162E820D1C".to_string(),
             "E31D1B22F059933E".to_string(),
-        );
-        let expected_ciphertext: u64 = u64::from_str_radix(&"7C7EE7162E820D1C", 16).ok().unwrap();
-        let expected_decrypted: u64 = u64::from_str_radix(&"7772A5DC17CC382C", 16).ok().unwrap();
+        )
+        .unwrap();
+        let expected_ciphertext: u64 = u64::from_str_radix("7C7EE7162E820D1C", 16).ok().unwrap();
+        let expected_decrypted: u64 = u64::from_str_radix("7772A5DC17CC382C", 16).ok().unwrap();
         assert_eq!(ciphertext, expected_ciphertext);
         assert_eq!(decrypted, expected_decrypted);
     }
@@ -293,13 +467,15 @@ mod tests {
         let ciphertext = des_encrypt(
             "b268ed282a85a2ad".to_string(),
             "07511c6c9929cd75".to_string(),
-        );
+        )
+        .unwrap();
         let decrypted = des_decrypt(
             "34B57D714D88E29C".to_string(),
             "07511c6c9929cd75".to_string(),
-        );
-        let expected_ciphertext: u64 = u64::from_str_radix(&"34B57D714D88E29C", 16).ok().unwrap();
-        let expected_decrypted: u64 = u64::from_str_radix(&"b268ed282a85a2ad", 16).ok().unwrap();
+        )
+        .unwrap();
+        let expected_ciphertext: u64 = u64::from_str_radix("34B57D714D88E29C", 16).ok().unwrap();
+        let expected_decrypted: u64 = u64::from_str_radix("b268ed282a85a2ad", 16).ok().unwrap();
         assert_eq!(ciphertext, expected_ciphertext);
         assert_eq!(decrypted, expected_decrypted);
     }
@@ -309,14 +485,111 @@ mod tests {
         let ciphertext = des_encrypt(
             "7e9591c91639ee65".to_string(),
             "c37ac5759520cd15".to_string(),
-        );
+        )
+        .unwrap();
         let decrypted = des_decrypt(
             "DC3C688EE9C561E6".to_string(),
             "c37ac5759520cd15".to_string(),
-        );
-        let expected_ciphertext: u64 = u64::from_str_radix(&"DC3C688EE9C561E6", 16).ok().unwrap();
-        let expected_decrypted: u64 = u64::from_str_radix(&"7e9591c91639ee65", 16).ok().unwrap();
+        )
+        .unwrap();
+        let expected_ciphertext: u64 = u64::from_str_radix("DC3C688EE9C561E6", 16).ok().unwrap();
+        let expected_decrypted: u64 = u64::from_str_radix("7e9591c91639ee65", 16).ok().unwrap();
         assert_eq!(ciphertext, expected_ciphertext);
         assert_eq!(decrypted, expected_decrypted);
     }
+
+    #[test]
+    fn des_block_api_matches_traced_api() {
+        let des = Des::new(0x133457799BBCDFF1);
+        let mut des_log_table = vec![];
+        let ciphertext = des.encrypt_block_traced(0x0123456789ABCDEF, &mut des_log_table);
+        assert_eq!(ciphertext, 0x85e813540f0ab405);
+        assert_eq!(des.encrypt_block(0x0123456789ABCDEF), ciphertext);
+        assert_eq!(des.decrypt_block(ciphertext), 0x0123456789ABCDEF);
+        // IP, K+, 16 rounds, IP-1
+        assert_eq!(des_log_table.len(), 19);
+    }
+
+    #[test]
+    fn des_from_bytes_matches_u64_key() {
+        let key: [u8; 8] = [0x0f, 0x15, 0x71, 0xc9, 0x47, 0xd9, 0xe8, 0x59];
+        let des = Des::from_bytes(key);
+        assert_eq!(des, Des::new(0x0f1571c947d9e859));
+        assert_eq!(des.encrypt_block(0x02468aceeca86420), 0xda02ce3a89ecac3b);
+    }
+
+    #[test]
+    fn des_encrypt_rejects_non_hex_plaintext() {
+        let result = des_encrypt(
+            "0123456789ABCDEZ".to_string(),
+            "133457799BBCDFF1".to_string(),
+        );
+        assert_eq!(
+            result,
+            Err(DesError::InvalidHex("0123456789ABCDEZ".to_string()))
+        );
+    }
+
+    #[test]
+    fn des_encrypt_rejects_18_digit_key() {
+        let result = des_encrypt(
+            "0123456789ABCDEF".to_string(),
+            "133457799BBCDFF100".to_string(),
+        );
+        assert_eq!(
+            result,
+            Err(DesError::InvalidKeyLength {
+                expected: 8,
+                actual: 9
+            })
+        );
+    }
+
+    #[test]
+    fn des_encrypt_rejects_odd_length_hex() {
+        for key in ["133457799BBCDFF", "133457799BBCDFF10"] {
+            assert_eq!(
+                des_encrypt("0123456789ABCDEF".to_string(), key.to_string()),
+                Err(DesError::InvalidHex(key.to_string()))
+            );
+        }
+        assert_eq!(
+            des_decrypt(
+                "0123456789ABCDE".to_string(),
+                "133457799BBCDFF1".to_string()
+            ),
+            Err(DesError::InvalidHex("0123456789ABCDE".to_string()))
+        );
+    }
+
+    #[test]
+    fn des_decrypt_rejects_short_block() {
+        let result = des_decrypt("85e81354".to_string(), "133457799BBCDFF1".to_string());
+        assert_eq!(
+            result,
+            Err(DesError::InvalidBlockLength {
+                expected: 8,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    fn des_debug_shows_kcv_not_key() {
+        assert_eq!(
+            format!("{:?}", Des::new(0x0123456789abcdef)),
+            "Des { kcv: d5d44f, .. }"
+        );
+    }
+
+    #[test]
+    fn des_from_slice_rejects_wrong_key_length() {
+        assert_eq!(
+            Des::from_slice(&[0; 7]),
+            Err(DesError::InvalidKeyLength {
+                expected: 8,
+                actual: 7
+            })
+        );
+    }
 }
@@ -0,0 +1,72 @@
+use std::fmt;
+
+use crate::weak_keys::WeakKeyClass;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DesError {
+    /// Input contains characters other than hex digits.
+    InvalidHex(String),
+    /// Key has the wrong number of bytes (hex input is counted in bytes, two digits each).
+    InvalidKeyLength { expected: usize, actual: usize },
+    /// Key length is none of the lengths an algorithm with several key sizes accepts.
+    UnsupportedKeyLength {
+        supported: &'static [usize],
+        actual: usize,
+    },
+    /// Data is not a whole number of blocks, or a block has the wrong size.
+    InvalidBlockLength { expected: usize, actual: usize },
+    /// Padding could not be removed after decryption.
+    InvalidPadding,
+    /// Key byte at `byte_index` does not have odd parity.
+    ParityFailure { byte_index: usize },
+    /// An option is outside the range the operation supports.
+    InvalidArgument(String),
+    /// Key (or one of its DES components) is weak, semi-weak or possibly weak.
+    WeakKey(WeakKeyClass),
+    /// A key check value does not match the key.
+    KcvMismatch,
+    /// The operating system random number generator failed.
+    RandomUnavailable(String),
+    /// Reading input or writing output failed.
+    Io(String),
+}
+
+impl fmt::Display for DesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DesError::InvalidHex(input) => write!(f, "invalid hex input: {:?}", input),
+            DesError::InvalidKeyLength { expected, actual } => write!(
+                f,
+                "invalid key length: expected {} bytes, got {}",
+                expected, actual
+            ),
+            DesError::UnsupportedKeyLength { supported, actual } => {
+                let supported: Vec<String> = supported.iter().map(usize::to_string).collect();
+                write!(
+                    f,
+                    "invalid key length: expected {} bytes, got {}",
+                    supported.join(" or "),
+                    actual
+                )
+            }
+            DesError::InvalidBlockLength { expected, actual } => write!(
+                f,
+                "invalid block length: expected {} bytes, got {}",
+                expected, actual
+            ),
+            DesError::InvalidPadding => write!(f, "invalid padding"),
+            DesError::ParityFailure { byte_index } => {
+                write!(f, "key byte {} does not have odd parity", byte_index)
+            }
+            DesError::InvalidArgument(reason) => write!(f, "invalid argument: {}", reason),
+            DesError::WeakKey(class) => write!(f, "refusing {} DES key", class),
+            DesError::KcvMismatch => write!(f, "key check value does not match the key"),
+            DesError::RandomUnavailable(reason) => {
+                write!(f, "random number generator unavailable: {}", reason)
+            }
+            DesError::Io(reason) => write!(f, "I/O error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for DesError {}
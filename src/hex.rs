@@ -0,0 +1,45 @@
+use crate::error::DesError;
+
+pub fn check_hex(input: &str) -> Result<(), DesError> {
+    if !input.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(DesError::InvalidHex(input.to_string()));
+    }
+    return Ok(());
+}
+
+pub fn decode(input: &str) -> Result<Vec<u8>, DesError> {
+    check_hex(input)?;
+    if !input.len().is_multiple_of(2) {
+        return Err(DesError::InvalidHex(input.to_string()));
+    }
+    let bytes = (0..input.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&input[index..index + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| DesError::InvalidHex(input.to_string()))?;
+    return Ok(bytes);
+}
+
+pub fn encode(bytes: &[u8]) -> String {
+    return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+
+    #[test]
+    fn decode_and_encode_round_trip() {
+        let bytes = decode("0123456789ABCDEF").unwrap();
+        assert_eq!(bytes, vec![0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef]);
+        assert_eq!(encode(&bytes), "0123456789abcdef");
+    }
+
+    #[test]
+    fn decode_rejects_non_hex_and_odd_length() {
+        assert_eq!(decode("0g"), Err(DesError::InvalidHex("0g".to_string())));
+        assert_eq!(decode("+1"), Err(DesError::InvalidHex("+1".to_string())));
+        assert_eq!(decode("abc"), Err(DesError::InvalidHex("abc".to_string())));
+    }
+}
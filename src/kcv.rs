@@ -0,0 +1,86 @@
+use crate::{
+    ct::constant_time_eq,
+    encrypt::{BlockCipher, Des},
+    error::DesError,
+    tdes::TripleDes,
+};
+
+pub const KCV_LENGTH: usize = 3;
+
+/// The zero block encrypted under a single (8-byte), double (16-byte) or triple (24-byte)
+/// length key. A key check value is a prefix of it, usually the first 3 bytes.
+pub fn key_check_block(key: &[u8]) -> Result<[u8; 8], DesError> {
+    let check_block = match key.len() {
+        8 => Des::from_slice(key)?.encrypt_block(0),
+        16 | 24 => TripleDes::from_slice(key)?.encrypt_block(0),
+        _ => {
+            return Err(DesError::UnsupportedKeyLength {
+                supported: &[8, 16, 24],
+                actual: key.len(),
+            })
+        }
+    };
+    return Ok(check_block.to_be_bytes());
+}
+
+pub fn key_check_value(key: &[u8]) -> Result<[u8; KCV_LENGTH], DesError> {
+    let check_block = key_check_block(key)?;
+    let mut kcv: [u8; KCV_LENGTH] = [0; KCV_LENGTH];
+    kcv.copy_from_slice(&check_block[..KCV_LENGTH]);
+    return Ok(kcv);
+}
+
+/// Checks a supplied KCV of 2 to 8 bytes against the key.
+pub fn verify_key_check_value(key: &[u8], kcv: &[u8]) -> Result<bool, DesError> {
+    if !(2..=8).contains(&kcv.len()) {
+        return Err(DesError::InvalidArgument(format!(
+            "KCV must be 2 to 8 bytes, got {}",
+            kcv.len()
+        )));
+    }
+    let check_block = key_check_block(key)?;
+    return Ok(constant_time_eq(&check_block[..kcv.len()], kcv));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::kcv::{key_check_value, verify_key_check_value};
+
+    #[test]
+    fn kcv_single_length_key() {
+        let key = decode("0123456789ABCDEF").unwrap();
+        assert_eq!(encode(&key_check_value(&key).unwrap()), "d5d44f");
+    }
+
+    #[test]
+    fn kcv_double_and_triple_length_keys() {
+        let double_key = decode("0123456789ABCDEFFEDCBA9876543210").unwrap();
+        assert_eq!(encode(&key_check_value(&double_key).unwrap()), "08d7b4");
+        let triple_key = decode("0123456789ABCDEFFEDCBA98765432100123456789ABCDEF").unwrap();
+        assert_eq!(key_check_value(&triple_key), key_check_value(&double_key));
+    }
+
+    #[test]
+    fn kcv_verify() {
+        let key = decode("0123456789ABCDEFFEDCBA9876543210").unwrap();
+        assert!(verify_key_check_value(&key, &decode("08d7b4").unwrap()).unwrap());
+        assert!(!verify_key_check_value(&key, &decode("08d7b5").unwrap()).unwrap());
+        assert!(matches!(
+            verify_key_check_value(&key, &[0x08]),
+            Err(DesError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn kcv_rejects_bad_key_length() {
+        assert_eq!(
+            key_check_value(&[0; 12]),
+            Err(DesError::UnsupportedKeyLength {
+                supported: &[8, 16, 24],
+                actual: 12
+            })
+        );
+    }
+}
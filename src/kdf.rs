@@ -0,0 +1,254 @@
+use md5::Md5;
+use sha1::{Digest, Sha1};
+
+use crate::{
+    cbc::{cbc_decrypt, cbc_encrypt},
+    encrypt::Des,
+    error::DesError,
+    padding::{pad, unpad, Padding},
+    tdes::TripleDes,
+};
+
+/// Hash behind the PKCS#5 v1.5 PBES1 schemes that use DES.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pbes1Hash {
+    /// pbeWithMD5AndDES-CBC
+    Md5,
+    /// pbeWithSHA1AndDES-CBC
+    Sha1,
+}
+
+/// Encryption schemes PBES2 can pair with PBKDF2 (RFC 8018 B.2.1 and B.2.2).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pbes2Cipher {
+    /// desCBC, 8-byte key
+    DesCbc,
+    /// des-EDE3-CBC, 24-byte key
+    DesEde3Cbc,
+}
+
+fn hash(hash: Pbes1Hash, data: &[u8]) -> Vec<u8> {
+    return match hash {
+        Pbes1Hash::Md5 => Md5::digest(data).to_vec(),
+        Pbes1Hash::Sha1 => Sha1::digest(data).to_vec(),
+    };
+}
+
+/// PBKDF1 as used by PBES1: the password and salt hashed `iterations` times. The first
+/// 8 bytes of the digest are the DES key and the next 8 the CBC IV.
+pub fn pbkdf1(
+    hash_function: Pbes1Hash,
+    password: &[u8],
+    salt: &[u8; 8],
+    iterations: u32,
+) -> Result<([u8; 8], u64), DesError> {
+    if iterations == 0 {
+        return Err(DesError::InvalidArgument(
+            "iteration count must be at least 1".to_string(),
+        ));
+    }
+    let mut derived_key = [password, salt].concat();
+    for _ in 0..iterations {
+        derived_key = hash(hash_function, &derived_key);
+    }
+    let mut key: [u8; 8] = [0; 8];
+    key.copy_from_slice(&derived_key[..8]);
+    let iv = u64::from_be_bytes(derived_key[8..16].try_into().unwrap());
+    return Ok((key, iv));
+}
+
+/// PBKDF2 with HMAC-SHA1, the PRF PKCS#5 v2 defaults to.
+pub fn pbkdf2_hmac_sha1(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    length: usize,
+) -> Result<Vec<u8>, DesError> {
+    if iterations == 0 {
+        return Err(DesError::InvalidArgument(
+            "iteration count must be at least 1".to_string(),
+        ));
+    }
+    let mut derived_key = vec![0; length];
+    pbkdf2::pbkdf2_hmac::<Sha1>(password, salt, iterations, &mut derived_key);
+    return Ok(derived_key);
+}
+
+/// PBES1 encryption: DES-CBC under the PBKDF1 key and IV, with PKCS#5 padding.
+pub fn pbes1_encrypt(
+    hash_function: Pbes1Hash,
+    password: &[u8],
+    salt: &[u8; 8],
+    iterations: u32,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    let (key, iv) = pbkdf1(hash_function, password, salt, iterations)?;
+    return cbc_encrypt(&Des::from_bytes(key), iv, &pad(plaintext, Padding::Pkcs7)?);
+}
+
+/// PBES1 decryption. A wrong password almost always shows up as `InvalidPadding`.
+pub fn pbes1_decrypt(
+    hash_function: Pbes1Hash,
+    password: &[u8],
+    salt: &[u8; 8],
+    iterations: u32,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    let (key, iv) = pbkdf1(hash_function, password, salt, iterations)?;
+    return unpad(
+        &cbc_decrypt(&Des::from_bytes(key), iv, ciphertext)?,
+        Padding::Pkcs7,
+    );
+}
+
+fn pbes2_crypt(
+    cipher: Pbes2Cipher,
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    iv: u64,
+    data: &[u8],
+    encrypt: bool,
+) -> Result<Vec<u8>, DesError> {
+    let key_length = match cipher {
+        Pbes2Cipher::DesCbc => 8,
+        Pbes2Cipher::DesEde3Cbc => 24,
+    };
+    let key = pbkdf2_hmac_sha1(password, salt, iterations, key_length)?;
+    return match (cipher, encrypt) {
+        (Pbes2Cipher::DesCbc, true) => cbc_encrypt(&Des::from_slice(&key)?, iv, data),
+        (Pbes2Cipher::DesCbc, false) => cbc_decrypt(&Des::from_slice(&key)?, iv, data),
+        (Pbes2Cipher::DesEde3Cbc, true) => cbc_encrypt(&TripleDes::from_slice(&key)?, iv, data),
+        (Pbes2Cipher::DesEde3Cbc, false) => cbc_decrypt(&TripleDes::from_slice(&key)?, iv, data),
+    };
+}
+
+/// PBES2 encryption: CBC under a PBKDF2-HMAC-SHA1 key, with PKCS#5 padding. Unlike PBES1
+/// the IV is not derived from the password and has to be stored next to the salt.
+pub fn pbes2_encrypt(
+    cipher: Pbes2Cipher,
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    iv: u64,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    let padded_plaintext = pad(plaintext, Padding::Pkcs7)?;
+    return pbes2_crypt(
+        cipher,
+        password,
+        salt,
+        iterations,
+        iv,
+        &padded_plaintext,
+        true,
+    );
+}
+
+pub fn pbes2_decrypt(
+    cipher: Pbes2Cipher,
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    iv: u64,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    let padded_plaintext = pbes2_crypt(cipher, password, salt, iterations, iv, ciphertext, false)?;
+    return unpad(&padded_plaintext, Padding::Pkcs7);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::kdf::{
+        pbes1_decrypt, pbes1_encrypt, pbes2_decrypt, pbes2_encrypt, pbkdf1, pbkdf2_hmac_sha1,
+        Pbes1Hash, Pbes2Cipher,
+    };
+
+    const SALT: [u8; 8] = [0x7d, 0x60, 0x43, 0x5f, 0x02, 0xe9, 0xe0, 0xae];
+    const PLAINTEXT: &[u8] = b"Now is the time for all ";
+
+    #[test]
+    fn pbkdf2_rfc_vectors() {
+        // RFC 6070
+        assert_eq!(
+            encode(&pbkdf2_hmac_sha1(b"password", b"salt", 1, 20).unwrap()),
+            "0c60c80f961f0e71f3a9b524af6012062fe037a6"
+        );
+        assert_eq!(
+            encode(&pbkdf2_hmac_sha1(b"password", b"salt", 4096, 20).unwrap()),
+            "4b007901b765489abead49d926f721d065a429c1"
+        );
+        // RFC 3962 Appendix B
+        assert_eq!(
+            encode(&pbkdf2_hmac_sha1(b"password", b"ATHENA.MIT.EDUraeburn", 1200, 16).unwrap()),
+            "5c08eb61fdf71e4e4ec3cf6ba1f5512b"
+        );
+    }
+
+    #[test]
+    fn pbkdf1_derives_key_and_iv() {
+        let (key, iv) = pbkdf1(Pbes1Hash::Md5, b"password", &SALT, 2048).unwrap();
+        assert_eq!(encode(&key), "b880f25bc30b3e9b");
+        assert_eq!(iv, 0xcfa9ec1f4d74d689);
+        let (key, iv) = pbkdf1(Pbes1Hash::Sha1, b"password", &SALT, 2048).unwrap();
+        assert_eq!(encode(&key), "30e69252758e5346");
+        assert_eq!(iv, 0x7c1c1ab9c454a688);
+    }
+
+    #[test]
+    fn pbes1_round_trips_openssl_ciphertext() {
+        let md5_ciphertext =
+            decode("ccc9bf08901de779cfddf6ba1baa93aa5ceff8e1cd8e5d4c56855bc3870a3b0f").unwrap();
+        let sha1_ciphertext =
+            decode("40b88e37b552fb89f36793daec095b3892b9235bc528faecb02889b695712926").unwrap();
+        for (hash, ciphertext) in [
+            (Pbes1Hash::Md5, md5_ciphertext),
+            (Pbes1Hash::Sha1, sha1_ciphertext),
+        ] {
+            assert_eq!(
+                pbes1_encrypt(hash, b"password", &SALT, 2048, PLAINTEXT).unwrap(),
+                ciphertext
+            );
+            assert_eq!(
+                pbes1_decrypt(hash, b"password", &SALT, 2048, &ciphertext).unwrap(),
+                PLAINTEXT
+            );
+        }
+    }
+
+    #[test]
+    fn pbes2_round_trips_openssl_ciphertext() {
+        let iv = 0x1234567890abcdef;
+        let des_ciphertext =
+            decode("9aeedcb1a6277c430e4bd2c834fdbb8b1aef51d4e52cbea6f4dffc4cf78ce708").unwrap();
+        let tdes_ciphertext =
+            decode("8c76adf22021d4417ab17a68fba74dfd44a751a5dd2b953b61bcbda79d6cbffe").unwrap();
+        for (cipher, ciphertext) in [
+            (Pbes2Cipher::DesCbc, des_ciphertext),
+            (Pbes2Cipher::DesEde3Cbc, tdes_ciphertext),
+        ] {
+            assert_eq!(
+                pbes2_encrypt(cipher, b"password", &SALT, 2048, iv, PLAINTEXT).unwrap(),
+                ciphertext
+            );
+            assert_eq!(
+                pbes2_decrypt(cipher, b"password", &SALT, 2048, iv, &ciphertext).unwrap(),
+                PLAINTEXT
+            );
+        }
+    }
+
+    #[test]
+    fn zero_iterations_rejected() {
+        assert!(matches!(
+            pbkdf1(Pbes1Hash::Md5, b"password", &SALT, 0),
+            Err(DesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            pbkdf2_hmac_sha1(b"password", &SALT, 0, 8),
+            Err(DesError::InvalidArgument(_))
+        ));
+    }
+}
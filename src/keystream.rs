@@ -0,0 +1,20 @@
+/// A block-cipher keystream that can be positioned at any byte offset.
+pub trait SeekableKeystream {
+    /// The 64-bit keystream block covering bytes `8 * block_index..8 * block_index + 8`.
+    fn keystream_block(&mut self, block_index: u64) -> u64;
+
+    /// Current byte offset into the keystream.
+    fn position(&self) -> u64;
+
+    fn seek(&mut self, byte_offset: u64);
+
+    /// XORs the keystream into `data` starting at the current position and advances past it.
+    fn apply_keystream(&mut self, data: &mut [u8]) {
+        for byte in data.iter_mut() {
+            let position = self.position();
+            let keystream_block = self.keystream_block(position / 8);
+            *byte ^= (keystream_block >> (56 - 8 * (position % 8))) as u8;
+            self.seek(position + 1);
+        }
+    }
+}
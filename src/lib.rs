@@ -0,0 +1,39 @@
+#![allow(clippy::needless_return)]
+
+pub mod binary_pads;
+pub mod cbc;
+pub mod cfb;
+pub mod cipher;
+pub mod cmac;
+pub mod crypt;
+pub mod ct;
+pub mod ctr;
+pub mod desx;
+pub mod dukpt;
+pub mod emv;
+pub mod encrypt;
+pub mod error;
+pub mod hex;
+pub mod kcv;
+pub mod kdf;
+pub mod keystream;
+pub mod lm;
+pub mod logging;
+pub mod mac;
+pub mod ntlm;
+pub mod ofb;
+pub mod padding;
+pub mod parity;
+pub mod pcbc;
+pub mod permutation_tables;
+pub mod pin;
+pub mod random;
+pub mod tdes;
+pub mod vnc;
+pub mod weak_keys;
+
+pub use cipher::{Cipher, CipherKind};
+pub use desx::DesX;
+pub use encrypt::{BlockCipher, Des};
+pub use error::DesError;
+pub use tdes::TripleDes;
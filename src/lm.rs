@@ -0,0 +1,92 @@
+use crate::{encrypt::Des, error::DesError, parity::expand_56_bit_key};
+
+/// The constant both password halves encrypt.
+pub const LM_MAGIC: &[u8; 8] = b"KGS!@#$%";
+pub const LM_MAX_PASSWORD_LENGTH: usize = 14;
+
+/// LAN Manager hash: the uppercased password, zero-padded to 14 bytes, is split into two
+/// 7-byte halves that each become a DES key encrypting `KGS!@#$%`. Windows never stores
+/// an LM hash for passwords over 14 characters, so they are rejected.
+pub fn lm_hash(password: &str) -> Result<[u8; 16], DesError> {
+    if !password.is_ascii() {
+        return Err(DesError::InvalidArgument(
+            "LM hash password must be ASCII".to_string(),
+        ));
+    }
+    if password.len() > LM_MAX_PASSWORD_LENGTH {
+        return Err(DesError::InvalidArgument(format!(
+            "LM hash password must be at most {} characters, got {}",
+            LM_MAX_PASSWORD_LENGTH,
+            password.len()
+        )));
+    }
+    let mut padded_password: [u8; LM_MAX_PASSWORD_LENGTH] = [0; LM_MAX_PASSWORD_LENGTH];
+    padded_password[..password.len()].copy_from_slice(password.to_ascii_uppercase().as_bytes());
+
+    let magic_block = u64::from_be_bytes(*LM_MAGIC);
+    let mut hash: [u8; 16] = [0; 16];
+    for (half, hash_half) in padded_password
+        .chunks_exact(7)
+        .zip(hash.chunks_exact_mut(8))
+    {
+        let des = Des::from_bytes(expand_56_bit_key(half.try_into().unwrap()));
+        hash_half.copy_from_slice(&des.encrypt_block(magic_block).to_be_bytes());
+    }
+    return Ok(hash);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DesError;
+    use crate::hex::encode;
+    use crate::lm::lm_hash;
+
+    #[test]
+    fn lm_hash_known_values() {
+        assert_eq!(
+            encode(&lm_hash("SecREt01").unwrap()),
+            "ff3750bcc2b22412c2265b23734e0dac"
+        );
+        assert_eq!(
+            encode(&lm_hash("password").unwrap()),
+            "e52cac67419a9a224a3b108f3fa6cb6d"
+        );
+        assert_eq!(
+            encode(&lm_hash("abcdefghijklmn").unwrap()),
+            "e0c510199cc66abd8c51ec214bebdea1"
+        );
+    }
+
+    #[test]
+    fn lm_hash_empty_half_is_constant() {
+        assert_eq!(
+            encode(&lm_hash("").unwrap()),
+            "aad3b435b51404eeaad3b435b51404ee"
+        );
+        // passwords of 7 characters or fewer leave the second half empty
+        assert_eq!(
+            encode(&lm_hash("Password123").unwrap()[..8]),
+            encode(&lm_hash("password").unwrap()[..8])
+        );
+        assert_eq!(
+            encode(&lm_hash("password").unwrap()[..8]),
+            encode(&lm_hash("PASSWOR").unwrap()[..8])
+        );
+        assert_eq!(
+            encode(&lm_hash("passwor").unwrap()[8..]),
+            "aad3b435b51404ee"
+        );
+    }
+
+    #[test]
+    fn lm_hash_rejects_long_or_non_ascii_passwords() {
+        assert!(matches!(
+            lm_hash("fifteen chars!!"),
+            Err(DesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            lm_hash("pässword"),
+            Err(DesError::InvalidArgument(_))
+        ));
+    }
+}
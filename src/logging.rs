@@ -13,7 +13,7 @@ pub struct DesLog {
 
 pub fn _print_u64(label: &str, block: u64) {
     // println!("{}{}", label, format!("{:064b}", block));
-    println!("{}{}", label, format!("{:016x}", block));
+    println!("{}{:016x}", label, block);
 }
 
 pub fn populate_kplus_log_table(des_log_table: &mut Vec<DesLog>, block: u64) {
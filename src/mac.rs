@@ -0,0 +1,185 @@
+use crate::{
+    cbc::cbc_encrypt,
+    encrypt::{BlockCipher, Des},
+    error::DesError,
+};
+
+/// ISO/IEC 9797-1 padding methods.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MacPadding {
+    /// Zeros up to the block boundary; an empty message becomes one zero block.
+    Method1,
+    /// A `0x80` byte followed by zeros, always added.
+    Method2,
+    /// The message length in bits as a leading block, then method 1 zeros.
+    Method3,
+}
+
+pub fn pad_mac_data(data: &[u8], padding: MacPadding) -> Vec<u8> {
+    let mut padded: Vec<u8> = vec![];
+    if padding == MacPadding::Method3 {
+        padded.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+    }
+    padded.extend_from_slice(data);
+    if padding == MacPadding::Method2 {
+        padded.push(0x80);
+    }
+    let zero_fill = (8 - padded.len() % 8) % 8;
+    padded.resize(padded.len() + zero_fill, 0);
+    if padded.is_empty() {
+        padded.resize(8, 0);
+    }
+    return padded;
+}
+
+fn truncate_mac(block: u64, mac_length: usize) -> Result<Vec<u8>, DesError> {
+    if !(4..=8).contains(&mac_length) {
+        return Err(DesError::InvalidArgument(format!(
+            "MAC length must be 4 to 8 bytes, got {}",
+            mac_length
+        )));
+    }
+    return Ok(block.to_be_bytes()[..mac_length].to_vec());
+}
+
+/// CBC over the padded message with a zero IV, returning the last block `H_q`.
+fn cbc_mac_block<C: BlockCipher>(
+    cipher: &C,
+    data: &[u8],
+    padding: MacPadding,
+) -> Result<u64, DesError> {
+    let ciphertext = cbc_encrypt(cipher, 0, &pad_mac_data(data, padding))?;
+    let mut last_block: [u8; 8] = [0; 8];
+    last_block.copy_from_slice(&ciphertext[ciphertext.len() - 8..]);
+    return Ok(u64::from_be_bytes(last_block));
+}
+
+/// MAC algorithm 1: plain CBC-MAC, usable with DES or Triple DES.
+pub fn mac_algorithm_1<C: BlockCipher>(
+    cipher: &C,
+    data: &[u8],
+    padding: MacPadding,
+    mac_length: usize,
+) -> Result<Vec<u8>, DesError> {
+    return truncate_mac(cbc_mac_block(cipher, data, padding)?, mac_length);
+}
+
+/// MAC algorithm 2: CBC-MAC whose last block is encrypted once more under `final_cipher`.
+pub fn mac_algorithm_2<C: BlockCipher, F: BlockCipher>(
+    cipher: &C,
+    final_cipher: &F,
+    data: &[u8],
+    padding: MacPadding,
+    mac_length: usize,
+) -> Result<Vec<u8>, DesError> {
+    let last_block = cbc_mac_block(cipher, data, padding)?;
+    return truncate_mac(final_cipher.encrypt_block(last_block), mac_length);
+}
+
+/// MAC algorithm 3: CBC-MAC under `cipher`, then decrypt under `second_cipher` and encrypt
+/// under `cipher` again. With single DES keys this is the ANSI X9.19 Retail MAC.
+pub fn mac_algorithm_3<C: BlockCipher, S: BlockCipher>(
+    cipher: &C,
+    second_cipher: &S,
+    data: &[u8],
+    padding: MacPadding,
+    mac_length: usize,
+) -> Result<Vec<u8>, DesError> {
+    let last_block = cbc_mac_block(cipher, data, padding)?;
+    let output_block = cipher.encrypt_block(second_cipher.decrypt_block(last_block));
+    return truncate_mac(output_block, mac_length);
+}
+
+/// ANSI X9.19 Retail MAC with a 16-byte `K || K'` key, padding method 1 and a full 8-byte MAC.
+pub fn retail_mac(key: &[u8], data: &[u8]) -> Result<Vec<u8>, DesError> {
+    if key.len() != 16 {
+        return Err(DesError::InvalidKeyLength {
+            expected: 16,
+            actual: key.len(),
+        });
+    }
+    let des = Des::from_slice(&key[..8])?;
+    let second_des = Des::from_slice(&key[8..])?;
+    return mac_algorithm_3(&des, &second_des, data, MacPadding::Method1, 8);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encrypt::Des;
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::mac::{
+        mac_algorithm_1, mac_algorithm_2, mac_algorithm_3, pad_mac_data, retail_mac, MacPadding,
+    };
+    use crate::tdes::TripleDes;
+
+    // ISO/IEC 9797-1 Annex B, "Now is the time for all " with K and K'
+    const KEY: u64 = 0x0123456789ABCDEF;
+    const SECOND_KEY: u64 = 0xFEDCBA9876543210;
+    const DATA: &[u8; 24] = b"Now is the time for all ";
+
+    #[test]
+    fn mac_padding_methods() {
+        assert_eq!(
+            encode(&pad_mac_data(b"ABCDE", MacPadding::Method1)),
+            "4142434445000000"
+        );
+        assert_eq!(
+            encode(&pad_mac_data(b"", MacPadding::Method1)),
+            "0000000000000000"
+        );
+        assert_eq!(
+            encode(&pad_mac_data(b"ABCDEFGH", MacPadding::Method2)),
+            "41424344454647488000000000000000"
+        );
+        assert_eq!(
+            encode(&pad_mac_data(b"ABCDE", MacPadding::Method3)),
+            "00000000000000284142434445000000"
+        );
+    }
+
+    #[test]
+    fn mac_algorithm_1_annex_b() {
+        let des = Des::new(KEY);
+        let mac = mac_algorithm_1(&des, DATA, MacPadding::Method1, 8).unwrap();
+        assert_eq!(encode(&mac), "70a30640cc76dd8b");
+        let mac = mac_algorithm_1(&des, DATA, MacPadding::Method2, 8).unwrap();
+        assert_eq!(encode(&mac), "10e1f0f108341b6d");
+    }
+
+    #[test]
+    fn mac_algorithm_3_is_x9_19_retail_mac() {
+        let des = Des::new(KEY);
+        let second_des = Des::new(SECOND_KEY);
+        let mac = mac_algorithm_3(&des, &second_des, DATA, MacPadding::Method1, 8).unwrap();
+        assert_eq!(encode(&mac), "a1c72e74ea3fa9b6");
+        let key = decode("0123456789ABCDEFFEDCBA9876543210").unwrap();
+        assert_eq!(retail_mac(&key, DATA).unwrap(), mac);
+    }
+
+    #[test]
+    fn mac_algorithm_2_encrypts_last_block_under_second_key() {
+        let des = Des::new(KEY);
+        let second_des = Des::new(SECOND_KEY);
+        let mac = mac_algorithm_2(&des, &second_des, DATA, MacPadding::Method1, 8).unwrap();
+        assert_eq!(encode(&mac), "541567cbbae5d014");
+    }
+
+    #[test]
+    fn mac_algorithm_1_with_tdes() {
+        let tdes = TripleDes::new_ede2(KEY, SECOND_KEY);
+        let mac = mac_algorithm_1(&tdes, DATA, MacPadding::Method1, 8).unwrap();
+        assert_eq!(encode(&mac), "93462a6db9b4a4d1");
+    }
+
+    #[test]
+    fn mac_is_truncated_to_requested_length() {
+        let des = Des::new(KEY);
+        let mac = mac_algorithm_1(&des, DATA, MacPadding::Method1, 4).unwrap();
+        assert_eq!(encode(&mac), "70a30640");
+        assert!(matches!(
+            mac_algorithm_1(&des, DATA, MacPadding::Method1, 9),
+            Err(DesError::InvalidArgument(_))
+        ));
+    }
+}
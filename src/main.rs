@@ -1,14 +1,311 @@
-mod binary_pads;
-mod encrypt;
-mod logging;
-mod permutation_tables;
-use clap::Parser;
-use encrypt::{des_decrypt, des_encrypt};
+#![allow(clippy::needless_return)]
+
+use base64::Engine;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use des_algo::cbc::{cbc_cs_decrypt, cbc_cs_encrypt, cbc_decrypt, cbc_encrypt, CiphertextStealing};
+use des_algo::cfb::{cfb_decrypt, cfb_encrypt, CfbSegment};
+use des_algo::cipher::{self, Cipher};
+use des_algo::ctr::{ctr_decrypt, ctr_encrypt};
+use des_algo::encrypt::{
+    blocks_to_bytes, bytes_to_blocks, des_decrypt, des_encrypt, parse_hex_block, parse_hex_key,
+    BlockCipher,
+};
+use des_algo::error::DesError;
+use des_algo::hex;
+use des_algo::kcv::{key_check_value, verify_key_check_value};
+use des_algo::lm::lm_hash;
+use des_algo::ofb::{ofb_decrypt, ofb_encrypt};
+use des_algo::padding::{pad, unpad, Padding};
+use des_algo::parity::check_parity;
+use des_algo::pcbc::{pcbc_decrypt, pcbc_encrypt};
+use des_algo::pin::{
+    build_pin_block, decrypt_pin_block, encrypt_pin_block, parse_pin_block, translate_pin_block,
+    PinBlockFormat,
+};
+use des_algo::random::{random_iv, random_key};
+use des_algo::weak_keys::classify_key;
+use std::io::Write;
+use std::process::ExitCode;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Encrypt every block independently and show the round tables
+    Ecb,
+    /// Cipher block chaining
+    Cbc,
+    /// CBC with ciphertext stealing, NIST CS1 block order
+    CbcCs1,
+    /// CBC with ciphertext stealing, NIST CS2 block order
+    CbcCs2,
+    /// CBC with ciphertext stealing, NIST CS3 block order
+    CbcCs3,
+    /// Propagating cipher block chaining (Kerberos v4)
+    Pcbc,
+    /// Cipher feedback, 1-bit segments
+    Cfb1,
+    /// Cipher feedback, 8-bit segments
+    Cfb8,
+    /// Cipher feedback, 64-bit segments
+    Cfb64,
+    /// Output feedback
+    Ofb,
+    /// Counter mode, the IV is the initial counter block
+    Ctr,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum CipherKind {
+    /// Single DES, 8-byte key
+    Des,
+    /// Triple DES (EDE), 16 or 24-byte key
+    Tdes,
+    /// DES-X key whitening, 24-byte key (K, pre-whitening K1, post-whitening K2)
+    Desx,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum PaddingKind {
+    /// No padding, plaintext must be whole 8-byte blocks
+    None,
+    /// PKCS#5 / PKCS#7
+    #[value(alias = "pkcs5")]
+    Pkcs7,
+    /// ISO/IEC 7816-4 (0x80 then zeros)
+    Iso7816,
+    /// ANSI X9.23 (zeros then length)
+    AnsiX923,
+    /// ISO 10126 (random bytes then length)
+    Iso10126,
+    /// Zero bytes, ambiguous if the plaintext ends in zeros
+    Zero,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum KeyLength {
+    /// 8 bytes, one DES key
+    Single,
+    /// 16 bytes, two-key triple DES (K1, K2, K1)
+    Double,
+    /// 24 bytes, three-key triple DES
+    Triple,
+}
+
+impl KeyLength {
+    fn bytes(self) -> usize {
+        return match self {
+            KeyLength::Single => 8,
+            KeyLength::Double => 16,
+            KeyLength::Triple => 24,
+        };
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Hex,
+    Base64,
+    /// Raw bytes on stdout, everything else goes to stderr
+    Raw,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum PinFormatKind {
+    /// ISO 9564 format 0 (ANSI X9.8), PIN padded with F and XORed with the PAN
+    Iso0,
+    /// ISO 9564 format 1, PIN padded with random nibbles, no PAN
+    Iso1,
+    /// ISO 9564 format 3, PIN padded with random A-F nibbles and XORed with the PAN
+    Iso3,
+}
+
+impl From<PinFormatKind> for PinBlockFormat {
+    fn from(pin_format_kind: PinFormatKind) -> PinBlockFormat {
+        return match pin_format_kind {
+            PinFormatKind::Iso0 => PinBlockFormat::Iso0,
+            PinFormatKind::Iso1 => PinBlockFormat::Iso1,
+            PinFormatKind::Iso3 => PinBlockFormat::Iso3,
+        };
+    }
+}
+
+impl From<PaddingKind> for Padding {
+    fn from(padding_kind: PaddingKind) -> Padding {
+        return match padding_kind {
+            PaddingKind::None => Padding::None,
+            PaddingKind::Pkcs7 => Padding::Pkcs7,
+            PaddingKind::Iso7816 => Padding::Iso7816,
+            PaddingKind::AnsiX923 => Padding::AnsiX923,
+            PaddingKind::Iso10126 => Padding::Iso10126,
+            PaddingKind::Zero => Padding::Zero,
+        };
+    }
+}
+
+impl From<CipherKind> for cipher::CipherKind {
+    fn from(cipher_kind: CipherKind) -> cipher::CipherKind {
+        return match cipher_kind {
+            CipherKind::Des => cipher::CipherKind::Des,
+            CipherKind::Tdes => cipher::CipherKind::TripleDes,
+            CipherKind::Desx => cipher::CipherKind::DesX,
+        };
+    }
+}
 
 /// Simple program to encrypt plaintext using DES algorithm, show processes and then decrpyt to validate.
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
-struct Args {
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    encrypt: Option<EncryptArgs>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compute or verify the key check value (first 3 bytes of the encrypted zero block)
+    Kcv(KcvArgs),
+    /// Generate an odd-parity CSPRNG key with no weak, semi-weak, possibly weak or repeated parts
+    Keygen(KeygenArgs),
+    /// Compute the LAN Manager hash of a password of up to 14 ASCII characters
+    LmHash(LmHashArgs),
+    /// Build, parse or translate ISO 9564 PIN blocks
+    #[command(subcommand)]
+    PinBlock(PinBlockCommand),
+    /// Encrypt twice under the same key and show the round tables; weak keys give the plaintext back
+    WeakKeyDemo(WeakKeyDemoArgs),
+}
+
+#[derive(Args, Debug)]
+struct WeakKeyDemoArgs {
+    /// Plaintext in hex code
+    #[arg(short, long, default_value = "0123456789abcdef")]
+    plaintext: String,
+
+    /// key in hex code, e.g. the weak key 0101010101010101
+    #[arg(short, long, default_value = "0101010101010101")]
+    key: String,
+}
+
+#[derive(Args, Debug)]
+struct KeygenArgs {
+    /// key length
+    #[arg(short, long, value_enum, default_value_t = KeyLength::Single)]
+    length: KeyLength,
+
+    /// output format of the key and IV
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Hex)]
+    format: OutputFormat,
+
+    /// also print the key check value
+    #[arg(long)]
+    kcv: bool,
+
+    /// also generate a random 8-byte IV
+    #[arg(long)]
+    iv: bool,
+}
+
+#[derive(Args, Debug)]
+struct LmHashArgs {
+    /// password, uppercased before hashing
+    #[arg(short, long)]
+    password: String,
+}
+
+#[derive(Subcommand, Debug)]
+enum PinBlockCommand {
+    /// Build a PIN block, encrypted when a key is given
+    Build(PinBlockBuildArgs),
+    /// Recover the PIN from a PIN block, decrypting it first when a key is given
+    Parse(PinBlockParseArgs),
+    /// Re-encrypt an encrypted PIN block under another key, optionally in another format
+    Translate(PinBlockTranslateArgs),
+}
+
+#[derive(Args, Debug)]
+struct PinBlockBuildArgs {
+    /// PIN of 4 to 12 digits
+    #[arg(long)]
+    pin: String,
+
+    /// primary account number, needed for formats iso0 and iso3
+    #[arg(long)]
+    pan: Option<String>,
+
+    /// PIN block format
+    #[arg(short, long, value_enum, default_value_t = PinFormatKind::Iso0)]
+    format: PinFormatKind,
+
+    /// single, double or triple-length PIN encryption key in hex code
+    #[arg(short, long)]
+    key: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct PinBlockParseArgs {
+    /// PIN block in hex code
+    #[arg(short, long)]
+    block: String,
+
+    /// primary account number, needed for formats iso0 and iso3
+    #[arg(long)]
+    pan: Option<String>,
+
+    /// PIN block format
+    #[arg(short, long, value_enum, default_value_t = PinFormatKind::Iso0)]
+    format: PinFormatKind,
+
+    /// single, double or triple-length PIN encryption key in hex code
+    #[arg(short, long)]
+    key: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct PinBlockTranslateArgs {
+    /// encrypted PIN block in hex code
+    #[arg(short, long)]
+    block: String,
+
+    /// primary account number, needed for formats iso0 and iso3
+    #[arg(long)]
+    pan: Option<String>,
+
+    /// key the PIN block is encrypted under, in hex code
+    #[arg(long)]
+    from_key: String,
+
+    /// format of the incoming PIN block
+    #[arg(long, value_enum, default_value_t = PinFormatKind::Iso0)]
+    from_format: PinFormatKind,
+
+    /// key to encrypt the translated PIN block under, in hex code
+    #[arg(long)]
+    to_key: String,
+
+    /// format of the translated PIN block, the incoming format when omitted
+    #[arg(long, value_enum)]
+    to_format: Option<PinFormatKind>,
+}
+
+#[derive(Args, Debug)]
+struct KcvArgs {
+    /// single, double or triple-length key in hex code
+    #[arg(short, long)]
+    key: String,
+
+    /// expected KCV in hex code, exits with an error when it does not match
+    #[arg(long)]
+    verify: Option<String>,
+
+    /// reject keys whose bytes do not all have odd parity (FIPS 46-3)
+    #[arg(long)]
+    strict_parity: bool,
+}
+
+#[derive(Args, Debug)]
+struct EncryptArgs {
     /// Plaintext in hex code
     #[arg(short, long)]
     plaintext: String,
@@ -16,39 +313,491 @@ struct Args {
     /// key for encryption and decryption in hex code
     #[arg(short, long)]
     key: String,
+
+    /// block cipher to run the mode with
+    #[arg(short, long, value_enum, default_value_t = CipherKind::Des)]
+    cipher: CipherKind,
+
+    /// block cipher mode of operation
+    #[arg(short, long, value_enum, default_value_t = Mode::Ecb)]
+    mode: Mode,
+
+    /// padding for the block modes (ecb, cbc, pcbc)
+    #[arg(long, value_enum, default_value_t = PaddingKind::None)]
+    padding: PaddingKind,
+
+    /// initialization vector in hex code, random when omitted
+    #[arg(long)]
+    iv: Option<String>,
+
+    /// number of low IV bits used as the counter in CTR mode, the rest is the nonce
+    #[arg(long, default_value_t = 32)]
+    counter_bits: u32,
+
+    /// reject keys whose bytes do not all have odd parity (FIPS 46-3)
+    #[arg(long)]
+    strict_parity: bool,
+
+    /// refuse weak, semi-weak and possibly weak keys instead of warning about them
+    #[arg(long)]
+    reject_weak_keys: bool,
 }
 
-fn check_string_is_ascii_hexdigit(s: String) -> bool {
-    return s.chars().all(|c| c.is_ascii_hexdigit());
+fn exit_code(error: &DesError) -> ExitCode {
+    // clap exits with 2 on usage errors, so these start at 3
+    return match error {
+        DesError::InvalidHex(_) => ExitCode::from(3),
+        DesError::InvalidKeyLength { .. } => ExitCode::from(4),
+        DesError::InvalidBlockLength { .. } => ExitCode::from(5),
+        DesError::InvalidPadding => ExitCode::from(6),
+        DesError::ParityFailure { .. } => ExitCode::from(7),
+        DesError::InvalidArgument(_) => ExitCode::from(8),
+        DesError::RandomUnavailable(_) => ExitCode::from(9),
+        DesError::KcvMismatch => ExitCode::from(10),
+        DesError::WeakKey(_) => ExitCode::from(11),
+        DesError::UnsupportedKeyLength { .. } => ExitCode::from(12),
+        DesError::Io(_) => ExitCode::from(13),
+    };
 }
 
-fn main() {
-    let args = Args::parse();
-    let plaintext_input = args.plaintext;
-    let key_input = args.key;
+fn check_key_parity(cipher_kind: CipherKind, key_input: &str) -> Result<(), DesError> {
+    let key = hex::decode(key_input)?;
+    return match cipher_kind {
+        // only the DES key of DES-X has parity bits, the whitening keys are plain 64-bit values
+        CipherKind::Desx => check_parity(&key[..key.len().min(8)]),
+        CipherKind::Des | CipherKind::Tdes => check_parity(&key),
+    };
+}
 
-    if !check_string_is_ascii_hexdigit(plaintext_input.clone()) {
-        panic!("plaintext is not hexdigit");
+fn check_weak_keys(
+    cipher_kind: CipherKind,
+    key_input: &str,
+    reject_weak_keys: bool,
+) -> Result<(), DesError> {
+    let key = hex::decode(key_input)?;
+    let des_keys: Vec<&[u8]> = match cipher_kind {
+        CipherKind::Des | CipherKind::Desx => key.chunks_exact(8).take(1).collect(),
+        CipherKind::Tdes => key.chunks_exact(8).collect(),
+    };
+    for des_key in des_keys {
+        let key_block = bytes_to_blocks(des_key)?[0];
+        if let Some(class) = classify_key(key_block) {
+            if reject_weak_keys {
+                return Err(DesError::WeakKey(class));
+            }
+            eprintln!("⚠️ warning: {:016x} is a {} DES key", key_block, class);
+        }
     }
-    if !check_string_is_ascii_hexdigit(key_input.clone()) {
-        panic!("key is not hexdigit");
+    return Ok(());
+}
+
+fn get_iv(iv_input: Option<String>) -> Result<u64, DesError> {
+    let iv: u64 = match iv_input {
+        Some(iv_input) => parse_hex_block(&iv_input)?,
+        None => random_iv()?,
+    };
+    println!("🎲 iv: {:016x}", iv);
+    return Ok(iv);
+}
+
+fn run_traced_ecb(plaintext: &[u8], key_input: &str) -> Result<(Vec<u8>, Vec<u8>), DesError> {
+    let mut ciphertext_blocks: Vec<u64> = vec![];
+    let mut decrypted_blocks: Vec<u64> = vec![];
+    for plaintext_block in bytes_to_blocks(plaintext)? {
+        let ciphertext = des_encrypt(format!("{:016x}", plaintext_block), key_input.to_string())?;
+        let decrypted = des_decrypt(format!("{:016x}", ciphertext), key_input.to_string())?;
+        ciphertext_blocks.push(ciphertext);
+        decrypted_blocks.push(decrypted);
+    }
+    return Ok((
+        blocks_to_bytes(&ciphertext_blocks),
+        blocks_to_bytes(&decrypted_blocks),
+    ));
+}
+
+fn run_mode(
+    mode: Mode,
+    cipher: &Cipher,
+    iv: u64,
+    counter_bits: u32,
+    plaintext: &[u8],
+) -> Result<(Vec<u8>, Vec<u8>), DesError> {
+    return match mode {
+        Mode::Ecb => {
+            let ciphertext_blocks: Vec<u64> = bytes_to_blocks(plaintext)?
+                .iter()
+                .map(|block| cipher.encrypt_block(*block))
+                .collect();
+            let decrypted_blocks: Vec<u64> = ciphertext_blocks
+                .iter()
+                .map(|block| cipher.decrypt_block(*block))
+                .collect();
+            Ok((
+                blocks_to_bytes(&ciphertext_blocks),
+                blocks_to_bytes(&decrypted_blocks),
+            ))
+        }
+        Mode::Cbc => {
+            let ciphertext = cbc_encrypt(cipher, iv, plaintext)?;
+            let decrypted = cbc_decrypt(cipher, iv, &ciphertext)?;
+            Ok((ciphertext, decrypted))
+        }
+        Mode::CbcCs1 | Mode::CbcCs2 | Mode::CbcCs3 => {
+            let variant = match mode {
+                Mode::CbcCs1 => CiphertextStealing::Cs1,
+                Mode::CbcCs2 => CiphertextStealing::Cs2,
+                _ => CiphertextStealing::Cs3,
+            };
+            let ciphertext = cbc_cs_encrypt(cipher, iv, variant, plaintext)?;
+            let decrypted = cbc_cs_decrypt(cipher, iv, variant, &ciphertext)?;
+            Ok((ciphertext, decrypted))
+        }
+        Mode::Pcbc => {
+            let ciphertext = pcbc_encrypt(cipher, iv, plaintext)?;
+            let decrypted = pcbc_decrypt(cipher, iv, &ciphertext)?;
+            Ok((ciphertext, decrypted))
+        }
+        Mode::Cfb1 | Mode::Cfb8 | Mode::Cfb64 => {
+            let segment = match mode {
+                Mode::Cfb1 => CfbSegment::Bits1,
+                Mode::Cfb8 => CfbSegment::Bits8,
+                _ => CfbSegment::Bits64,
+            };
+            let ciphertext = cfb_encrypt(cipher, iv, segment, plaintext);
+            let decrypted = cfb_decrypt(cipher, iv, segment, &ciphertext);
+            Ok((ciphertext, decrypted))
+        }
+        Mode::Ofb => {
+            let ciphertext = ofb_encrypt(cipher, iv, plaintext);
+            let decrypted = ofb_decrypt(cipher, iv, &ciphertext);
+            Ok((ciphertext, decrypted))
+        }
+        Mode::Ctr => {
+            let ciphertext = ctr_encrypt(cipher, iv, counter_bits, plaintext)?;
+            let decrypted = ctr_decrypt(cipher, iv, counter_bits, &ciphertext)?;
+            Ok((ciphertext, decrypted))
+        }
+    };
+}
+
+fn run_kcv(args: KcvArgs) -> Result<(), DesError> {
+    let key = hex::decode(&args.key)?;
+    if args.strict_parity {
+        check_parity(&key)?;
+    }
+    let kcv = key_check_value(&key)?;
+    println!("🔑 kcv: {}", hex::encode(&kcv));
+    if let Some(expected_kcv) = args.verify {
+        if !verify_key_check_value(&key, &hex::decode(&expected_kcv)?)? {
+            return Err(DesError::KcvMismatch);
+        }
+        println!("✅ kcv matches");
     }
+    return Ok(());
+}
+
+fn print_generated(label: &str, bytes: &[u8], format: OutputFormat) -> Result<(), DesError> {
+    match format {
+        OutputFormat::Hex => println!("{} {}", label, hex::encode(bytes)),
+        OutputFormat::Base64 => println!(
+            "{} {}",
+            label,
+            base64::engine::general_purpose::STANDARD.encode(bytes)
+        ),
+        OutputFormat::Raw => {
+            // flush too, a buffered write only fails once it reaches the pipe or file
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(bytes)
+                .and_then(|_| stdout.flush())
+                .map_err(|error| DesError::Io(error.to_string()))?
+        }
+    }
+    return Ok(());
+}
 
-    let plaintext_blocks = plaintext_input
-        .as_bytes()
-        .chunks(16)
-        .map(std::str::from_utf8)
-        .collect::<Result<Vec<&str>, _>>()
-        .unwrap();
+fn run_keygen(args: KeygenArgs) -> Result<(), DesError> {
+    let key = random_key(args.length.bytes())?;
+    print_generated("🔑 key:", &key, args.format)?;
+    if args.iv {
+        print_generated("🎲 iv:", &random_iv()?.to_be_bytes(), args.format)?;
+    }
+    if args.kcv {
+        let kcv = hex::encode(&key_check_value(&key)?);
+        match args.format {
+            OutputFormat::Raw => eprintln!("🔑 kcv: {}", kcv),
+            OutputFormat::Hex | OutputFormat::Base64 => println!("🔑 kcv: {}", kcv),
+        }
+    }
+    return Ok(());
+}
 
-    for index in 0..plaintext_blocks.len() {
-        let plaintext = format!("{:0<16}", plaintext_blocks[index]);
-        let ciphertext = des_encrypt(plaintext.to_string(), key_input.clone());
-        let ciphertext_string: String = format!("{:016x}", ciphertext);
-        println!("✅ ciphertext: {}\n", ciphertext_string);
+fn run_lm_hash(args: LmHashArgs) -> Result<(), DesError> {
+    println!("🔑 lm: {}", hex::encode(&lm_hash(&args.password)?));
+    return Ok(());
+}
+
+fn run_pin_block(command: PinBlockCommand) -> Result<(), DesError> {
+    match command {
+        PinBlockCommand::Build(args) => {
+            let pin_block = build_pin_block(args.format.into(), &args.pin, args.pan.as_deref())?;
+            println!("🔢 pin block: {:016x}", pin_block);
+            if let Some(key) = args.key {
+                let encrypted_pin_block = encrypt_pin_block(&hex::decode(&key)?, pin_block)?;
+                println!("🔒 encrypted pin block: {:016x}", encrypted_pin_block);
+            }
+        }
+        PinBlockCommand::Parse(args) => {
+            let mut pin_block = parse_hex_block(&args.block)?;
+            if let Some(key) = args.key {
+                pin_block = decrypt_pin_block(&hex::decode(&key)?, pin_block)?;
+                println!("🔢 pin block: {:016x}", pin_block);
+            }
+            let pin = parse_pin_block(args.format.into(), pin_block, args.pan.as_deref())?;
+            println!("✅ pin: {}", pin);
+        }
+        PinBlockCommand::Translate(args) => {
+            let translated_pin_block = translate_pin_block(
+                parse_hex_block(&args.block)?,
+                args.pan.as_deref(),
+                &hex::decode(&args.from_key)?,
+                args.from_format.into(),
+                &hex::decode(&args.to_key)?,
+                args.to_format.unwrap_or(args.from_format).into(),
+            )?;
+            println!("🔒 encrypted pin block: {:016x}", translated_pin_block);
+        }
+    }
+    return Ok(());
+}
+
+fn run_weak_key_demo(args: WeakKeyDemoArgs) -> Result<(), DesError> {
+    let key_block = parse_hex_key(&args.key)?;
+    match classify_key(key_block) {
+        Some(class) => println!("🔑 {:016x} is a {} key", key_block, class),
+        None => println!("🔑 {:016x} is not a weak key", key_block),
+    }
+    let ciphertext = des_encrypt(args.plaintext.clone(), args.key.clone())?;
+    println!("✅ ciphertext: {:016x}\n", ciphertext);
+    let encrypted_twice = des_encrypt(format!("{:016x}", ciphertext), args.key)?;
+    println!("✅ encrypted twice: {:016x}", encrypted_twice);
+    if encrypted_twice == parse_hex_block(&args.plaintext)? {
+        println!("⚠️ encryption is an involution under this key: encrypting twice gives back the plaintext");
+    } else {
+        println!("encrypting twice does not give back the plaintext");
+    }
+    return Ok(());
+}
+
+/// Encrypts and decrypts the plaintext, returning the ciphertext and the unpadded decryption.
+fn encrypt_and_decrypt(args: &EncryptArgs) -> Result<(Vec<u8>, Vec<u8>), DesError> {
+    let key_input = &args.key;
+
+    let mode_name = args
+        .mode
+        .to_possible_value()
+        .unwrap()
+        .get_name()
+        .to_string();
+    let padding = match args.mode {
+        Mode::Ecb | Mode::Cbc | Mode::Pcbc => Padding::from(args.padding),
+        // ciphertext stealing and the stream modes take the plaintext as is
+        Mode::CbcCs1
+        | Mode::CbcCs2
+        | Mode::CbcCs3
+        | Mode::Cfb1
+        | Mode::Cfb8
+        | Mode::Cfb64
+        | Mode::Ofb
+        | Mode::Ctr => {
+            if args.padding != PaddingKind::None {
+                return Err(DesError::InvalidArgument(format!(
+                    "--padding only applies to ecb, cbc and pcbc, not {}",
+                    mode_name
+                )));
+            }
+            Padding::None
+        }
+    };
+    if args.mode == Mode::Ecb && args.iv.is_some() {
+        return Err(DesError::InvalidArgument(
+            "--iv does not apply to ecb".to_string(),
+        ));
+    }
+
+    let plaintext = hex::decode(&args.plaintext)?;
+    let cipher = Cipher::from_hex_key(args.cipher.into(), key_input)?;
+    if args.strict_parity {
+        check_key_parity(args.cipher, key_input)?;
+    }
+    check_weak_keys(args.cipher, key_input, args.reject_weak_keys)?;
+    let plaintext = match padding {
+        Padding::None => plaintext,
+        _ => pad(&plaintext, padding)?,
+    };
+
+    let (ciphertext, decrypted) = if args.mode == Mode::Ecb && args.cipher == CipherKind::Des {
+        run_traced_ecb(&plaintext, key_input)?
+    } else {
+        let iv = match args.mode {
+            Mode::Ecb => 0,
+            _ => get_iv(args.iv.clone())?,
+        };
+        run_mode(args.mode, &cipher, iv, args.counter_bits, &plaintext)?
+    };
+    return Ok((ciphertext, unpad(&decrypted, padding)?));
+}
+
+fn run_encrypt(args: EncryptArgs) -> Result<(), DesError> {
+    let (ciphertext, decrypted) = encrypt_and_decrypt(&args)?;
+    println!("✅ ciphertext: {}\n", hex::encode(&ciphertext));
+    println!("✅ decrypted: {}", hex::encode(&decrypted));
+    return Ok(());
+}
+
+fn run(cli: Cli) -> Result<(), DesError> {
+    return match (cli.command, cli.encrypt) {
+        (Some(Command::Kcv(args)), _) => run_kcv(args),
+        (Some(Command::Keygen(args)), _) => run_keygen(args),
+        (Some(Command::LmHash(args)), _) => run_lm_hash(args),
+        (Some(Command::PinBlock(command)), _) => run_pin_block(command),
+        (Some(Command::WeakKeyDemo(args)), _) => run_weak_key_demo(args),
+        (None, Some(args)) => run_encrypt(args),
+        (None, None) => Err(DesError::InvalidArgument(
+            "expected --plaintext and --key or a subcommand".to_string(),
+        )),
+    };
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    if let Err(error) = run(cli) {
+        eprintln!("❌ error: {}", error);
+        return exit_code(&error);
+    }
+    return ExitCode::SUCCESS;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{encrypt_and_decrypt, exit_code, Cli, EncryptArgs};
+    use clap::Parser;
+    use des_algo::error::DesError;
+    use des_algo::hex::encode;
+    use std::process::ExitCode;
+
+    fn encrypt_args(args: &[&str]) -> EncryptArgs {
+        let cli = Cli::try_parse_from([&["des-algo"], args].concat()).unwrap();
+        return cli.encrypt.unwrap();
+    }
+
+    #[test]
+    fn traced_ecb_removes_pkcs7_padding() {
+        for (cipher, key) in [
+            ("des", "133457799bbcdff1"),
+            ("tdes", "133457799bbcdff10123456789abcdef"),
+        ] {
+            let args = encrypt_args(&["-p", "0011", "-k", key, "-c", cipher, "--padding", "pkcs7"]);
+            let (ciphertext, decrypted) = encrypt_and_decrypt(&args).unwrap();
+            assert_eq!(ciphertext.len(), 8);
+            assert_eq!(encode(&decrypted), "0011");
+        }
+    }
+
+    #[test]
+    fn traced_ecb_without_padding_takes_whole_blocks() {
+        let args = encrypt_args(&[
+            "-p",
+            "0011223344556677",
+            "-k",
+            "133457799bbcdff1",
+            "--padding",
+            "none",
+        ]);
+        let (_, decrypted) = encrypt_and_decrypt(&args).unwrap();
+        assert_eq!(encode(&decrypted), "0011223344556677");
+        let args = encrypt_args(&["-p", "00", "-k", "133457799bbcdff1", "--padding", "none"]);
+        assert_eq!(
+            encrypt_and_decrypt(&args),
+            Err(DesError::InvalidBlockLength {
+                expected: 8,
+                actual: 1
+            })
+        );
+    }
+
+    #[test]
+    fn error_exit_codes_do_not_clash_with_usage_errors() {
+        assert_eq!(
+            exit_code(&DesError::InvalidHex("0".to_string())),
+            ExitCode::from(3)
+        );
+        assert_ne!(exit_code(&DesError::InvalidPadding), ExitCode::from(2));
+        // a failed write is not a bad argument
+        assert_ne!(
+            exit_code(&DesError::Io("broken pipe".to_string())),
+            exit_code(&DesError::InvalidArgument("broken pipe".to_string()))
+        );
+    }
+
+    #[test]
+    fn padding_is_rejected_for_modes_without_it() {
+        for mode in ["ctr", "cbc-cs1", "cfb8", "ofb"] {
+            let args = encrypt_args(&[
+                "-p",
+                "0011",
+                "-k",
+                "133457799bbcdff1",
+                "-m",
+                mode,
+                "--iv",
+                "1234567890abcdef",
+                "--padding",
+                "pkcs7",
+            ]);
+            assert_eq!(
+                encrypt_and_decrypt(&args),
+                Err(DesError::InvalidArgument(format!(
+                    "--padding only applies to ecb, cbc and pcbc, not {}",
+                    mode
+                )))
+            );
+        }
+        let args = encrypt_args(&[
+            "-p",
+            "0011",
+            "-k",
+            "133457799bbcdff1",
+            "-m",
+            "ctr",
+            "--iv",
+            "1234567890abcdef",
+        ]);
+        assert_eq!(encode(&encrypt_and_decrypt(&args).unwrap().1), "0011");
+    }
 
-        let decrypted = des_decrypt(ciphertext_string.clone(), key_input.clone());
-        let decrypted_string: String = format!("{:016x}", decrypted);
-        println!("✅ decrypted: {}", decrypted_string);
+    #[test]
+    fn iv_is_rejected_for_ecb() {
+        for (cipher, key) in [
+            ("des", "133457799bbcdff1"),
+            ("desx", "133457799bbcdff11011121314151617a0a1a2a3a4a5a6a7"),
+        ] {
+            let args = encrypt_args(&[
+                "-p",
+                "0123456789abcdef",
+                "-k",
+                key,
+                "-c",
+                cipher,
+                "--iv",
+                "1234567890abcdef",
+            ]);
+            assert_eq!(
+                encrypt_and_decrypt(&args),
+                Err(DesError::InvalidArgument(
+                    "--iv does not apply to ecb".to_string()
+                ))
+            );
+        }
     }
 }
@@ -0,0 +1,331 @@
+use md4::Md4;
+use md5::Md5;
+use sha1::{Digest, Sha1};
+
+use crate::{
+    ct::constant_time_eq, encrypt::Des, error::DesError, hex, lm::lm_hash,
+    parity::expand_56_bit_key,
+};
+
+pub const RESPONSE_LENGTH: usize = 24;
+
+const MSCHAPV2_MAGIC_1: &[u8; 39] = b"Magic server to client signing constant";
+const MSCHAPV2_MAGIC_2: &[u8; 41] = b"Pad to make it do more than one iteration";
+
+/// MD4 of the UTF-16LE password, the NT password hash (NTOWFv1).
+pub fn nt_hash(password: &str) -> [u8; 16] {
+    let password_utf16: Vec<u8> = password.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    return Md4::digest(password_utf16).into();
+}
+
+/// DESL from MS-NLMP (ChallengeResponse in RFC 2759): the 16-byte hash is zero-padded to
+/// 21 bytes and each 7-byte third becomes a DES key that encrypts the challenge.
+pub fn challenge_response(password_hash: &[u8; 16], challenge: [u8; 8]) -> [u8; 24] {
+    let mut padded_hash: [u8; 21] = [0; 21];
+    padded_hash[..16].copy_from_slice(password_hash);
+    let challenge_block = u64::from_be_bytes(challenge);
+    let mut response: [u8; RESPONSE_LENGTH] = [0; RESPONSE_LENGTH];
+    for (key_56, response_part) in padded_hash
+        .chunks_exact(7)
+        .zip(response.chunks_exact_mut(8))
+    {
+        let des = Des::from_bytes(expand_56_bit_key(key_56.try_into().unwrap()));
+        response_part.copy_from_slice(&des.encrypt_block(challenge_block).to_be_bytes());
+    }
+    return response;
+}
+
+/// Checks a 24-byte response against the hash and challenge in constant time.
+pub fn verify_challenge_response(
+    password_hash: &[u8; 16],
+    challenge: [u8; 8],
+    response: &[u8],
+) -> Result<bool, DesError> {
+    if response.len() != RESPONSE_LENGTH {
+        return Err(DesError::InvalidBlockLength {
+            expected: RESPONSE_LENGTH,
+            actual: response.len(),
+        });
+    }
+    let expected_response = challenge_response(password_hash, challenge);
+    return Ok(constant_time_eq(&expected_response, response));
+}
+
+/// NTLMv1 LM and NT responses to the server challenge. Passwords without an LM hash (over
+/// 14 characters or not ASCII) send the NT response in the LM slot too, as MS-NLMP does
+/// with NoLMResponseNTLMv1.
+pub fn ntlmv1_responses(password: &str, server_challenge: [u8; 8]) -> ([u8; 24], [u8; 24]) {
+    let nt_response = challenge_response(&nt_hash(password), server_challenge);
+    let lm_response = match lm_hash(password) {
+        Ok(hash) => challenge_response(&hash, server_challenge),
+        Err(_) => nt_response,
+    };
+    return (lm_response, nt_response);
+}
+
+/// The challenge NTLMv1 with extended session security actually encrypts: the first 8 bytes
+/// of MD5(server challenge || client challenge).
+pub fn ntlmv1_ess_challenge(server_challenge: [u8; 8], client_challenge: [u8; 8]) -> [u8; 8] {
+    let digest = Md5::digest([server_challenge, client_challenge].concat());
+    return digest[..8].try_into().unwrap();
+}
+
+/// NTLMv1-ESS (NTLM2 session response) LM and NT responses. The LM response only carries
+/// the client challenge, zero-padded to 24 bytes.
+pub fn ntlmv1_ess_responses(
+    nt_hash: &[u8; 16],
+    server_challenge: [u8; 8],
+    client_challenge: [u8; 8],
+) -> ([u8; 24], [u8; 24]) {
+    let mut lm_response: [u8; RESPONSE_LENGTH] = [0; RESPONSE_LENGTH];
+    lm_response[..8].copy_from_slice(&client_challenge);
+    let challenge = ntlmv1_ess_challenge(server_challenge, client_challenge);
+    return (lm_response, challenge_response(nt_hash, challenge));
+}
+
+/// RFC 2759 ChallengeHash: SHA-1 of both challenges and the user name, cut to 8 bytes.
+pub fn mschapv2_challenge_hash(
+    peer_challenge: &[u8; 16],
+    authenticator_challenge: &[u8; 16],
+    username: &str,
+) -> [u8; 8] {
+    let mut sha1 = Sha1::new();
+    sha1.update(peer_challenge);
+    sha1.update(authenticator_challenge);
+    sha1.update(username.as_bytes());
+    return sha1.finalize()[..8].try_into().unwrap();
+}
+
+/// RFC 2759 GenerateNTResponse.
+pub fn mschapv2_nt_response(
+    nt_hash: &[u8; 16],
+    peer_challenge: &[u8; 16],
+    authenticator_challenge: &[u8; 16],
+    username: &str,
+) -> [u8; 24] {
+    let challenge = mschapv2_challenge_hash(peer_challenge, authenticator_challenge, username);
+    return challenge_response(nt_hash, challenge);
+}
+
+/// Authenticator side check of the peer's NT-Response.
+pub fn mschapv2_verify_nt_response(
+    nt_hash: &[u8; 16],
+    peer_challenge: &[u8; 16],
+    authenticator_challenge: &[u8; 16],
+    username: &str,
+    nt_response: &[u8],
+) -> Result<bool, DesError> {
+    let challenge = mschapv2_challenge_hash(peer_challenge, authenticator_challenge, username);
+    return verify_challenge_response(nt_hash, challenge, nt_response);
+}
+
+/// RFC 2759 GenerateAuthenticatorResponse, the `S=` string that proves to the peer that
+/// the authenticator knows the password hash too.
+pub fn mschapv2_authenticator_response(
+    nt_hash: &[u8; 16],
+    nt_response: &[u8; 24],
+    peer_challenge: &[u8; 16],
+    authenticator_challenge: &[u8; 16],
+    username: &str,
+) -> String {
+    let password_hash_hash = Md4::digest(nt_hash);
+    let mut sha1 = Sha1::new();
+    sha1.update(password_hash_hash);
+    sha1.update(nt_response);
+    sha1.update(MSCHAPV2_MAGIC_1);
+    let digest = sha1.finalize();
+    let challenge = mschapv2_challenge_hash(peer_challenge, authenticator_challenge, username);
+    let mut sha1 = Sha1::new();
+    sha1.update(digest);
+    sha1.update(challenge);
+    sha1.update(MSCHAPV2_MAGIC_2);
+    return format!("S={}", hex::encode(&sha1.finalize()).to_uppercase());
+}
+
+/// Peer side check of the authenticator's `S=` string; hex case does not matter.
+pub fn mschapv2_verify_authenticator_response(
+    nt_hash: &[u8; 16],
+    nt_response: &[u8; 24],
+    peer_challenge: &[u8; 16],
+    authenticator_challenge: &[u8; 16],
+    username: &str,
+    authenticator_response: &str,
+) -> bool {
+    let expected_response = mschapv2_authenticator_response(
+        nt_hash,
+        nt_response,
+        peer_challenge,
+        authenticator_challenge,
+        username,
+    );
+    return constant_time_eq(
+        expected_response.as_bytes(),
+        authenticator_response.to_uppercase().as_bytes(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::ntlm::{
+        challenge_response, mschapv2_authenticator_response, mschapv2_challenge_hash,
+        mschapv2_nt_response, mschapv2_verify_authenticator_response, mschapv2_verify_nt_response,
+        nt_hash, ntlmv1_ess_responses, ntlmv1_responses, verify_challenge_response,
+    };
+
+    // MS-NLMP 4.2.1
+    const SERVER_CHALLENGE: [u8; 8] = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+    const CLIENT_CHALLENGE: [u8; 8] = [0xaa; 8];
+
+    // RFC 2759 section 9.2
+    const RFC_2759_AUTHENTICATOR_CHALLENGE: &str = "5b5d7c7d7b3f2f3e3c2c602132262628";
+    const RFC_2759_PEER_CHALLENGE: &str = "21402324255e262a28295f2b3a337c7e";
+    const RFC_2759_NT_RESPONSE: &str = "82309ecd8d708b5ea08faa3981cd83544233114a3d85d6df";
+    const RFC_2759_AUTHENTICATOR_RESPONSE: &str = "S=407A5589115FD0D6209F510FE9C04566932CDA56";
+
+    fn rfc_2759_challenges() -> ([u8; 16], [u8; 16]) {
+        let peer_challenge = decode(RFC_2759_PEER_CHALLENGE).unwrap();
+        let authenticator_challenge = decode(RFC_2759_AUTHENTICATOR_CHALLENGE).unwrap();
+        return (
+            peer_challenge.try_into().unwrap(),
+            authenticator_challenge.try_into().unwrap(),
+        );
+    }
+
+    #[test]
+    fn nt_hash_known_values() {
+        assert_eq!(
+            encode(&nt_hash("Password")),
+            "a4f49c406510bdcab6824ee7c30fd852"
+        );
+        assert_eq!(
+            encode(&nt_hash("clientPass")),
+            "44ebba8d5312b8d611474411f56989ae"
+        );
+    }
+
+    #[test]
+    fn ntlmv1_ms_nlmp_vector() {
+        let (lm_response, nt_response) = ntlmv1_responses("Password", SERVER_CHALLENGE);
+        assert_eq!(
+            encode(&lm_response),
+            "98def7b87f88aa5dafe2df779688a172def11c7d5ccdef13"
+        );
+        assert_eq!(
+            encode(&nt_response),
+            "67c43011f30298a2ad35ece64f16331c44bdbed927841f94"
+        );
+        let hash = nt_hash("Password");
+        assert_eq!(
+            verify_challenge_response(&hash, SERVER_CHALLENGE, &nt_response),
+            Ok(true)
+        );
+        assert_eq!(
+            verify_challenge_response(&nt_hash("password"), SERVER_CHALLENGE, &nt_response),
+            Ok(false)
+        );
+        assert_eq!(
+            verify_challenge_response(&hash, SERVER_CHALLENGE, &nt_response[..16]),
+            Err(DesError::InvalidBlockLength {
+                expected: 24,
+                actual: 16
+            })
+        );
+    }
+
+    #[test]
+    fn ntlmv1_without_lm_hash_repeats_nt_response() {
+        for password in ["correct horse battery", "Pässword"] {
+            let (lm_response, nt_response) = ntlmv1_responses(password, SERVER_CHALLENGE);
+            assert_eq!(lm_response, nt_response);
+            if password.is_ascii() {
+                assert_eq!(
+                    encode(&nt_response),
+                    "5ac50948db9b9f5d412af05acb0e23598eb0304e97e8897b"
+                );
+            }
+            assert_eq!(
+                verify_challenge_response(&nt_hash(password), SERVER_CHALLENGE, &nt_response),
+                Ok(true)
+            );
+        }
+    }
+
+    #[test]
+    fn ntlmv1_ess_ms_nlmp_vector() {
+        let (lm_response, nt_response) =
+            ntlmv1_ess_responses(&nt_hash("Password"), SERVER_CHALLENGE, CLIENT_CHALLENGE);
+        assert_eq!(
+            encode(&lm_response),
+            "aaaaaaaaaaaaaaaa00000000000000000000000000000000"
+        );
+        assert_eq!(
+            encode(&nt_response),
+            "7537f803ae367128ca458204bde7caf81e97ed2683267232"
+        );
+    }
+
+    #[test]
+    fn mschapv2_rfc_2759_vector() {
+        let (peer_challenge, authenticator_challenge) = rfc_2759_challenges();
+        let hash = nt_hash("clientPass");
+        assert_eq!(
+            encode(&mschapv2_challenge_hash(
+                &peer_challenge,
+                &authenticator_challenge,
+                "User"
+            )),
+            "d02e4386bce91226"
+        );
+        let nt_response =
+            mschapv2_nt_response(&hash, &peer_challenge, &authenticator_challenge, "User");
+        assert_eq!(encode(&nt_response), RFC_2759_NT_RESPONSE);
+        assert_eq!(
+            mschapv2_verify_nt_response(
+                &hash,
+                &peer_challenge,
+                &authenticator_challenge,
+                "User",
+                &nt_response
+            ),
+            Ok(true)
+        );
+        assert_eq!(
+            mschapv2_verify_nt_response(
+                &hash,
+                &peer_challenge,
+                &authenticator_challenge,
+                "user",
+                &nt_response
+            ),
+            Ok(false)
+        );
+        assert_eq!(
+            mschapv2_authenticator_response(
+                &hash,
+                &nt_response,
+                &peer_challenge,
+                &authenticator_challenge,
+                "User"
+            ),
+            RFC_2759_AUTHENTICATOR_RESPONSE
+        );
+        assert!(mschapv2_verify_authenticator_response(
+            &hash,
+            &nt_response,
+            &peer_challenge,
+            &authenticator_challenge,
+            "User",
+            "S=407a5589115fd0d6209f510fe9c04566932cda56"
+        ));
+    }
+
+    #[test]
+    fn challenge_response_pads_hash_to_21_bytes() {
+        // the last key is five zero bytes of padding behind 2 hash bytes
+        let response = challenge_response(&[0; 16], SERVER_CHALLENGE);
+        assert_eq!(response[..8], response[8..16]);
+        assert_eq!(response[8..16], response[16..]);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{encrypt::BlockCipher, keystream::SeekableKeystream};
+
+/// Output feedback keystream: block `n` is the IV encrypted `n + 1` times.
+pub struct Ofb<'a, C: BlockCipher> {
+    cipher: &'a C,
+    iv: u64,
+    position: u64,
+    cached_block_index: u64,
+    cached_block: u64,
+}
+
+impl<'a, C: BlockCipher> Ofb<'a, C> {
+    pub fn new(cipher: &'a C, iv: u64) -> Ofb<'a, C> {
+        return Ofb {
+            cipher,
+            iv,
+            position: 0,
+            cached_block_index: 0,
+            cached_block: cipher.encrypt_block(iv),
+        };
+    }
+}
+
+impl<C: BlockCipher> SeekableKeystream for Ofb<'_, C> {
+    fn keystream_block(&mut self, block_index: u64) -> u64 {
+        if block_index < self.cached_block_index {
+            // the feedback chain only runs forwards, start again from the IV
+            self.cached_block_index = 0;
+            self.cached_block = self.cipher.encrypt_block(self.iv);
+        }
+        while self.cached_block_index < block_index {
+            self.cached_block = self.cipher.encrypt_block(self.cached_block);
+            self.cached_block_index += 1;
+        }
+        return self.cached_block;
+    }
+
+    fn position(&self) -> u64 {
+        return self.position;
+    }
+
+    fn seek(&mut self, byte_offset: u64) {
+        self.position = byte_offset;
+    }
+}
+
+pub fn ofb_encrypt<C: BlockCipher>(cipher: &C, iv: u64, plaintext: &[u8]) -> Vec<u8> {
+    let mut ciphertext = plaintext.to_vec();
+    Ofb::new(cipher, iv).apply_keystream(&mut ciphertext);
+    return ciphertext;
+}
+
+pub fn ofb_decrypt<C: BlockCipher>(cipher: &C, iv: u64, ciphertext: &[u8]) -> Vec<u8> {
+    return ofb_encrypt(cipher, iv, ciphertext);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encrypt::Des;
+    use crate::hex::encode;
+    use crate::keystream::SeekableKeystream;
+    use crate::ofb::{ofb_decrypt, ofb_encrypt, Ofb};
+
+    // FIPS 81 Appendix B, "Now is the time for all "
+    const FIPS_81_KEY: u64 = 0x0123456789abcdef;
+    const FIPS_81_IV: u64 = 0x1234567890abcdef;
+    const FIPS_81_PLAINTEXT: &[u8; 24] = b"Now is the time for all ";
+
+    #[test]
+    fn ofb_fips_81_vector() {
+        let des = Des::new(FIPS_81_KEY);
+        let ciphertext = ofb_encrypt(&des, FIPS_81_IV, FIPS_81_PLAINTEXT);
+        assert_eq!(
+            encode(&ciphertext),
+            "f3096249c7f46e5135f24a242eeb3d3f3d6d5be3255af8c3"
+        );
+        assert_eq!(
+            ofb_decrypt(&des, FIPS_81_IV, &ciphertext),
+            FIPS_81_PLAINTEXT
+        );
+    }
+
+    #[test]
+    fn ofb_seek_matches_sequential_keystream() {
+        let des = Des::new(FIPS_81_KEY);
+        let ciphertext = ofb_encrypt(&des, FIPS_81_IV, FIPS_81_PLAINTEXT);
+        let mut ofb = Ofb::new(&des, FIPS_81_IV);
+        let mut tail = ciphertext[13..].to_vec();
+        ofb.seek(13);
+        ofb.apply_keystream(&mut tail);
+        assert_eq!(tail, FIPS_81_PLAINTEXT[13..]);
+
+        let mut head = ciphertext[..5].to_vec();
+        ofb.seek(0);
+        ofb.apply_keystream(&mut head);
+        assert_eq!(head, FIPS_81_PLAINTEXT[..5]);
+        assert_eq!(ofb.position(), 5);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{error::DesError, random::fill_random};
+
+const BLOCK_SIZE: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Padding {
+    /// No padding, the input must already be a whole number of blocks.
+    None,
+    /// PKCS#7 (PKCS#5 for 8-byte blocks): `n` bytes of value `n`.
+    Pkcs7,
+    /// ISO/IEC 7816-4: a `0x80` byte followed by zeros.
+    Iso7816,
+    /// ANSI X9.23: zeros followed by the pad length.
+    AnsiX923,
+    /// ISO 10126: random bytes followed by the pad length.
+    Iso10126,
+    /// Zeros up to the block boundary, nothing when the input is already aligned.
+    Zero,
+}
+
+pub fn pad(data: &[u8], padding: Padding) -> Result<Vec<u8>, DesError> {
+    let pad_length = BLOCK_SIZE - data.len() % BLOCK_SIZE;
+    let mut padded = data.to_vec();
+    match padding {
+        Padding::None => {
+            if pad_length != BLOCK_SIZE {
+                return Err(DesError::InvalidBlockLength {
+                    expected: BLOCK_SIZE,
+                    actual: data.len(),
+                });
+            }
+        }
+        Padding::Pkcs7 => padded.resize(data.len() + pad_length, pad_length as u8),
+        Padding::Iso7816 => {
+            padded.push(0x80);
+            padded.resize(data.len() + pad_length, 0);
+        }
+        Padding::AnsiX923 => {
+            padded.resize(data.len() + pad_length - 1, 0);
+            padded.push(pad_length as u8);
+        }
+        Padding::Iso10126 => {
+            let mut random_bytes = vec![0; pad_length - 1];
+            fill_random(&mut random_bytes)?;
+            padded.extend_from_slice(&random_bytes);
+            padded.push(pad_length as u8);
+        }
+        Padding::Zero => {
+            if pad_length != BLOCK_SIZE {
+                padded.resize(data.len() + pad_length, 0);
+            }
+        }
+    }
+    return Ok(padded);
+}
+
+/// Strips `padding` from decrypted data, rejecting anything that `pad` could not have produced.
+pub fn unpad(data: &[u8], padding: Padding) -> Result<Vec<u8>, DesError> {
+    if padding == Padding::None {
+        return Ok(data.to_vec());
+    }
+    if !data.len().is_multiple_of(BLOCK_SIZE) {
+        return Err(DesError::InvalidBlockLength {
+            expected: BLOCK_SIZE,
+            actual: data.len(),
+        });
+    }
+    let unpadded_length = match padding {
+        Padding::None => data.len(),
+        Padding::Pkcs7 | Padding::AnsiX923 | Padding::Iso10126 => {
+            let pad_length = *data.last().ok_or(DesError::InvalidPadding)? as usize;
+            if pad_length == 0 || pad_length > BLOCK_SIZE {
+                return Err(DesError::InvalidPadding);
+            }
+            let filler = &data[data.len() - pad_length..data.len() - 1];
+            let filler_is_valid = match padding {
+                Padding::Pkcs7 => filler.iter().all(|byte| *byte as usize == pad_length),
+                Padding::AnsiX923 => filler.iter().all(|byte| *byte == 0),
+                _ => true,
+            };
+            if !filler_is_valid {
+                return Err(DesError::InvalidPadding);
+            }
+            data.len() - pad_length
+        }
+        Padding::Iso7816 => {
+            let last_block = &data[data.len().saturating_sub(BLOCK_SIZE)..];
+            let marker_index = last_block
+                .iter()
+                .rposition(|byte| *byte != 0)
+                .ok_or(DesError::InvalidPadding)?;
+            if last_block[marker_index] != 0x80 {
+                return Err(DesError::InvalidPadding);
+            }
+            data.len() - (last_block.len() - marker_index)
+        }
+        Padding::Zero => {
+            data.len()
+                - data
+                    .iter()
+                    .rev()
+                    .take(BLOCK_SIZE - 1)
+                    .take_while(|byte| **byte == 0)
+                    .count()
+        }
+    };
+    return Ok(data[..unpadded_length].to_vec());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::padding::{pad, unpad, Padding};
+
+    #[test]
+    fn pkcs7_pads_and_unpads() {
+        assert_eq!(
+            encode(&pad(b"ABCDE", Padding::Pkcs7).unwrap()),
+            "4142434445030303"
+        );
+        assert_eq!(
+            encode(&pad(b"ABCDEFGH", Padding::Pkcs7).unwrap()),
+            "41424344454647480808080808080808"
+        );
+        assert_eq!(
+            unpad(&decode("4142434445030303").unwrap(), Padding::Pkcs7).unwrap(),
+            b"ABCDE"
+        );
+    }
+
+    #[test]
+    fn iso7816_pads_and_unpads() {
+        assert_eq!(
+            encode(&pad(b"ABCDE", Padding::Iso7816).unwrap()),
+            "4142434445800000"
+        );
+        assert_eq!(
+            unpad(&decode("4142434445800000").unwrap(), Padding::Iso7816).unwrap(),
+            b"ABCDE"
+        );
+        assert_eq!(
+            unpad(&decode("4142434445464780").unwrap(), Padding::Iso7816).unwrap(),
+            b"ABCDEFG"
+        );
+    }
+
+    #[test]
+    fn ansi_x923_pads_and_unpads() {
+        assert_eq!(
+            encode(&pad(b"ABCDE", Padding::AnsiX923).unwrap()),
+            "4142434445000003"
+        );
+        assert_eq!(
+            unpad(&decode("4142434445000003").unwrap(), Padding::AnsiX923).unwrap(),
+            b"ABCDE"
+        );
+    }
+
+    #[test]
+    fn iso10126_ends_with_pad_length() {
+        let padded = pad(b"ABCDE", Padding::Iso10126).unwrap();
+        assert_eq!(padded.len(), 8);
+        assert_eq!(padded[7], 3);
+        assert_eq!(unpad(&padded, Padding::Iso10126).unwrap(), b"ABCDE");
+    }
+
+    #[test]
+    fn zero_padding_leaves_aligned_input_alone() {
+        assert_eq!(
+            encode(&pad(b"ABCDE", Padding::Zero).unwrap()),
+            "4142434445000000"
+        );
+        assert_eq!(pad(b"ABCDEFGH", Padding::Zero).unwrap(), b"ABCDEFGH");
+        assert_eq!(
+            unpad(&decode("4142434445000000").unwrap(), Padding::Zero).unwrap(),
+            b"ABCDE"
+        );
+    }
+
+    #[test]
+    fn none_requires_whole_blocks() {
+        assert_eq!(
+            pad(b"ABCDE", Padding::None),
+            Err(DesError::InvalidBlockLength {
+                expected: 8,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_padding_is_rejected() {
+        let cases = [
+            ("4142434445030203", Padding::Pkcs7),
+            ("4142434445464700", Padding::Pkcs7),
+            ("4142434445464709", Padding::Pkcs7),
+            ("4142434445010003", Padding::AnsiX923),
+            ("4142434445800100", Padding::Iso7816),
+            ("0000000000000000", Padding::Iso7816),
+            ("4142434445464700", Padding::Iso10126),
+        ];
+        for (padded, padding) in cases {
+            assert_eq!(
+                unpad(&decode(padded).unwrap(), padding),
+                Err(DesError::InvalidPadding),
+                "{} {:?}",
+                padded,
+                padding
+            );
+        }
+        assert_eq!(unpad(&[], Padding::Pkcs7), Err(DesError::InvalidPadding));
+    }
+}
@@ -0,0 +1,91 @@
+use crate::error::DesError;
+
+/// FIPS 46-3 reserves the least significant bit of every key byte so that the byte has an
+/// odd number of set bits. PC-1 drops these bits, so they never reach the key schedule.
+pub fn has_odd_parity(byte: u8) -> bool {
+    return byte.count_ones() % 2 == 1;
+}
+
+/// Fails with the index of the first key byte that has even parity.
+pub fn check_parity(key: &[u8]) -> Result<(), DesError> {
+    return match key.iter().position(|byte| !has_odd_parity(*byte)) {
+        Some(byte_index) => Err(DesError::ParityFailure { byte_index }),
+        None => Ok(()),
+    };
+}
+
+/// Sets the parity bit of every key byte, leaving the 7 key bits of each byte untouched.
+pub fn fix_parity(key: &[u8]) -> Vec<u8> {
+    return key
+        .iter()
+        .map(|byte| {
+            let key_bits = byte & 0xfe;
+            key_bits | ((key_bits.count_ones() as u8 + 1) & 1)
+        })
+        .collect();
+}
+
+/// Spreads 56 key bits over 8 bytes, 7 bits per byte, and sets the parity bits. LM and
+/// NTLM use this to turn 7-byte slices of a password hash into DES keys.
+pub fn expand_56_bit_key(key_56: &[u8; 7]) -> [u8; 8] {
+    let mut key_bits: [u8; 8] = [0; 8];
+    key_bits[1..].copy_from_slice(key_56);
+    let key_bits = u64::from_be_bytes(key_bits);
+    let mut key: [u8; 8] = [0; 8];
+    for (index, key_byte) in key.iter_mut().enumerate() {
+        *key_byte = (((key_bits >> (49 - 7 * index)) & 0x7f) as u8) << 1;
+    }
+    let key = fix_parity(&key);
+    return key.try_into().unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encrypt::Des;
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::parity::{check_parity, expand_56_bit_key, fix_parity, has_odd_parity};
+
+    #[test]
+    fn odd_parity_bytes() {
+        assert!(has_odd_parity(0x01));
+        assert!(has_odd_parity(0x13));
+        assert!(!has_odd_parity(0x00));
+        assert!(!has_odd_parity(0x12));
+    }
+
+    #[test]
+    fn check_parity_reports_first_bad_byte() {
+        assert_eq!(check_parity(&decode("133457799BBCDFF1").unwrap()), Ok(()));
+        assert_eq!(
+            check_parity(&decode("0023456789ABCDEF").unwrap()),
+            Err(DesError::ParityFailure { byte_index: 0 })
+        );
+        assert_eq!(
+            check_parity(&decode("0122456789ABCDEF").unwrap()),
+            Err(DesError::ParityFailure { byte_index: 1 })
+        );
+    }
+
+    #[test]
+    fn fix_parity_only_changes_parity_bits() {
+        let key = decode("0023456789ABCDEF").unwrap();
+        let fixed_key = fix_parity(&key);
+        assert_eq!(encode(&fixed_key), "0123456789abcdef");
+        assert_eq!(check_parity(&fixed_key), Ok(()));
+        // the key schedule ignores parity bits, so both keys encrypt identically
+        let des = Des::from_slice(&key).unwrap();
+        let fixed_des = Des::from_slice(&fixed_key).unwrap();
+        assert_eq!(
+            des.encrypt_block(0x0123456789ABCDEF),
+            fixed_des.encrypt_block(0x0123456789ABCDEF)
+        );
+    }
+
+    #[test]
+    fn expand_56_bit_key_spreads_7_bits_per_byte() {
+        assert_eq!(encode(&expand_56_bit_key(b"PASSWOR")), "5120546b34ba3da4");
+        assert_eq!(encode(&expand_56_bit_key(&[0; 7])), "0101010101010101");
+        assert_eq!(encode(&expand_56_bit_key(&[0xff; 7])), "fefefefefefefefe");
+    }
+}
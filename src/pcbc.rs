@@ -0,0 +1,101 @@
+use crate::{
+    encrypt::{blocks_to_bytes, bytes_to_blocks, BlockCipher},
+    error::DesError,
+};
+
+/// Propagating CBC: every ciphertext block is chained with both the previous plaintext and
+/// the previous ciphertext block, so a corrupted block garbles the rest of the message.
+pub fn pcbc_encrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: u64,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    let plaintext_blocks = bytes_to_blocks(plaintext)?;
+    let mut ciphertext_blocks: Vec<u64> = Vec::with_capacity(plaintext_blocks.len());
+    let mut feedback = iv;
+    for plaintext_block in plaintext_blocks {
+        let ciphertext_block = cipher.encrypt_block(plaintext_block ^ feedback);
+        ciphertext_blocks.push(ciphertext_block);
+        feedback = plaintext_block ^ ciphertext_block;
+    }
+    return Ok(blocks_to_bytes(&ciphertext_blocks));
+}
+
+pub fn pcbc_decrypt<C: BlockCipher>(
+    cipher: &C,
+    iv: u64,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, DesError> {
+    let ciphertext_blocks = bytes_to_blocks(ciphertext)?;
+    let mut plaintext_blocks: Vec<u64> = Vec::with_capacity(ciphertext_blocks.len());
+    let mut feedback = iv;
+    for ciphertext_block in ciphertext_blocks {
+        let plaintext_block = cipher.decrypt_block(ciphertext_block) ^ feedback;
+        plaintext_blocks.push(plaintext_block);
+        feedback = plaintext_block ^ ciphertext_block;
+    }
+    return Ok(blocks_to_bytes(&plaintext_blocks));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbc::{cbc_decrypt, cbc_encrypt};
+    use crate::encrypt::Des;
+    use crate::hex::encode;
+    use crate::pcbc::{pcbc_decrypt, pcbc_encrypt};
+
+    const KEY: u64 = 0x0123456789abcdef;
+    const IV: u64 = 0x1234567890abcdef;
+    const PLAINTEXT: &[u8; 32] = b"Now is the time for all good men";
+
+    #[test]
+    fn pcbc_known_answer() {
+        let des = Des::new(KEY);
+        let ciphertext = pcbc_encrypt(&des, IV, &PLAINTEXT[..24]).unwrap();
+        // the first block is identical to CBC
+        assert_eq!(
+            encode(&ciphertext),
+            "e5c7cdde872bf27ccb70b78c59494228265f223fc0c655a5"
+        );
+        assert_eq!(
+            pcbc_decrypt(&des, IV, &ciphertext).unwrap(),
+            PLAINTEXT[..24]
+        );
+    }
+
+    #[test]
+    fn cbc_error_stays_within_two_blocks() {
+        let des = Des::new(KEY);
+        let mut ciphertext = cbc_encrypt(&des, IV, PLAINTEXT).unwrap();
+        ciphertext[8] ^= 0x01;
+        let decrypted = cbc_decrypt(&des, IV, &ciphertext).unwrap();
+        assert_eq!(decrypted[..8], PLAINTEXT[..8]);
+        assert_ne!(decrypted[8..16], PLAINTEXT[8..16]);
+        assert_ne!(decrypted[16..24], PLAINTEXT[16..24]);
+        assert_eq!(decrypted[24..], PLAINTEXT[24..]);
+    }
+
+    #[test]
+    fn pcbc_error_propagates_to_the_end() {
+        let des = Des::new(KEY);
+        let mut ciphertext = pcbc_encrypt(&des, IV, PLAINTEXT).unwrap();
+        ciphertext[8] ^= 0x01;
+        let decrypted = pcbc_decrypt(&des, IV, &ciphertext).unwrap();
+        assert_eq!(decrypted[..8], PLAINTEXT[..8]);
+        assert_ne!(decrypted[8..16], PLAINTEXT[8..16]);
+        assert_ne!(decrypted[16..24], PLAINTEXT[16..24]);
+        assert_ne!(decrypted[24..], PLAINTEXT[24..]);
+    }
+
+    #[test]
+    fn pcbc_swapped_blocks_do_not_propagate() {
+        // known PCBC weakness: exchanging two adjacent ciphertext blocks leaves the rest intact
+        let des = Des::new(KEY);
+        let mut ciphertext = pcbc_encrypt(&des, IV, PLAINTEXT).unwrap();
+        let (first, second) = ciphertext.split_at_mut(16);
+        first[8..16].swap_with_slice(&mut second[..8]);
+        let decrypted = pcbc_decrypt(&des, IV, &ciphertext).unwrap();
+        assert_ne!(decrypted[8..24], PLAINTEXT[8..24]);
+        assert_eq!(decrypted[24..], PLAINTEXT[24..]);
+    }
+}
@@ -0,0 +1,347 @@
+use std::fmt;
+
+use crate::{encrypt::BlockCipher, error::DesError, random::fill_random, tdes::TripleDes};
+
+pub const MIN_PIN_LENGTH: usize = 4;
+pub const MAX_PIN_LENGTH: usize = 12;
+
+/// ISO 9564-1 PIN block formats. The first nibble of the PIN field is the format number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinBlockFormat {
+    /// PIN padded with `F`, XORed with 12 PAN digits (ANSI X9.8).
+    Iso0,
+    /// PIN padded with random nibbles, no PAN.
+    Iso1,
+    /// PIN padded with random `A` to `F` nibbles, XORed with 12 PAN digits.
+    Iso3,
+}
+
+impl PinBlockFormat {
+    fn control_nibble(self) -> u64 {
+        return match self {
+            PinBlockFormat::Iso0 => 0,
+            PinBlockFormat::Iso1 => 1,
+            PinBlockFormat::Iso3 => 3,
+        };
+    }
+}
+
+impl fmt::Display for PinBlockFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "ISO 9564 format {}", self.control_nibble());
+    }
+}
+
+fn digits(input: &str, name: &str) -> Result<Vec<u64>, DesError> {
+    return input
+        .chars()
+        .map(|c| {
+            c.to_digit(10).map(u64::from).ok_or_else(|| {
+                DesError::InvalidArgument(format!("{} must only contain digits", name))
+            })
+        })
+        .collect();
+}
+
+/// `0000` followed by the 12 rightmost PAN digits excluding the check digit, zero-padded
+/// on the left for short PANs. Format 1 does not use the PAN.
+fn pan_field(format: PinBlockFormat, pan: Option<&str>) -> Result<u64, DesError> {
+    if format == PinBlockFormat::Iso1 {
+        return Ok(0);
+    }
+    let pan =
+        pan.ok_or_else(|| DesError::InvalidArgument(format!("{} PIN blocks need a PAN", format)))?;
+    let pan_digits = digits(pan, "PAN")?;
+    if !(2..=19).contains(&pan_digits.len()) {
+        return Err(DesError::InvalidArgument(format!(
+            "PAN must be 2 to 19 digits, got {}",
+            pan_digits.len()
+        )));
+    }
+    let account_digits = &pan_digits[..pan_digits.len() - 1];
+    let account_digits = &account_digits[account_digits.len().saturating_sub(12)..];
+    return Ok(account_digits
+        .iter()
+        .fold(0, |field, digit| (field << 4) | digit));
+}
+
+/// Random fill nibbles for the PIN field: any value for format 1, `A` to `F` for format 3.
+fn random_fill(format: PinBlockFormat) -> Result<u64, DesError> {
+    let mut fill: u64 = 0;
+    match format {
+        PinBlockFormat::Iso0 => fill = u64::MAX,
+        PinBlockFormat::Iso1 => {
+            let mut bytes: [u8; 8] = [0; 8];
+            fill_random(&mut bytes)?;
+            fill = u64::from_be_bytes(bytes);
+        }
+        PinBlockFormat::Iso3 => {
+            let mut nibble_count = 0;
+            while nibble_count < 16 {
+                let mut byte: [u8; 1] = [0];
+                fill_random(&mut byte)?;
+                // 252 is the largest multiple of 6 that fits, so every nibble is uniform
+                if byte[0] < 252 {
+                    fill = (fill << 4) | (10 + (byte[0] % 6) as u64);
+                    nibble_count += 1;
+                }
+            }
+        }
+    }
+    return Ok(fill);
+}
+
+fn pin_block_with_fill(
+    format: PinBlockFormat,
+    pin: &str,
+    pan: Option<&str>,
+    fill: u64,
+) -> Result<u64, DesError> {
+    let pin_digits = digits(pin, "PIN")?;
+    if !(MIN_PIN_LENGTH..=MAX_PIN_LENGTH).contains(&pin_digits.len()) {
+        return Err(DesError::InvalidArgument(format!(
+            "PIN must be {} to {} digits, got {}",
+            MIN_PIN_LENGTH,
+            MAX_PIN_LENGTH,
+            pin_digits.len()
+        )));
+    }
+    let mut pin_field = (format.control_nibble() << 60) | ((pin_digits.len() as u64) << 56);
+    for (index, digit) in pin_digits.iter().enumerate() {
+        pin_field |= digit << (52 - 4 * index);
+    }
+    let fill_bits = 4 * (14 - pin_digits.len());
+    pin_field |= fill & ((1 << fill_bits) - 1);
+    return Ok(pin_field ^ pan_field(format, pan)?);
+}
+
+/// Builds a clear PIN block. `pan` is the full primary account number, needed for formats
+/// 0 and 3.
+pub fn build_pin_block(
+    format: PinBlockFormat,
+    pin: &str,
+    pan: Option<&str>,
+) -> Result<u64, DesError> {
+    return pin_block_with_fill(format, pin, pan, random_fill(format)?);
+}
+
+/// Recovers the PIN from a clear PIN block, checking the format nibble, the PIN length and
+/// digits and the fill. A block decrypted under the wrong key fails these checks.
+pub fn parse_pin_block(
+    format: PinBlockFormat,
+    pin_block: u64,
+    pan: Option<&str>,
+) -> Result<String, DesError> {
+    let pin_field = pin_block ^ pan_field(format, pan)?;
+    let invalid_block = |reason: &str| {
+        return Err(DesError::InvalidArgument(format!(
+            "not an {} PIN block: {}",
+            format, reason
+        )));
+    };
+    if pin_field >> 60 != format.control_nibble() {
+        return invalid_block("wrong control nibble");
+    }
+    let pin_length = ((pin_field >> 56) & 0xf) as usize;
+    if !(MIN_PIN_LENGTH..=MAX_PIN_LENGTH).contains(&pin_length) {
+        return invalid_block("PIN length out of range");
+    }
+    let nibble = |index: usize| (pin_field >> (52 - 4 * index)) & 0xf;
+    let mut pin = String::with_capacity(pin_length);
+    for index in 0..pin_length {
+        match char::from_digit(nibble(index) as u32, 10) {
+            Some(digit) => pin.push(digit),
+            None => return invalid_block("PIN digit out of range"),
+        }
+    }
+    let fill_is_valid = (pin_length..14).all(|index| match format {
+        PinBlockFormat::Iso0 => nibble(index) == 0xf,
+        PinBlockFormat::Iso1 => true,
+        PinBlockFormat::Iso3 => nibble(index) >= 0xa,
+    });
+    if !fill_is_valid {
+        return invalid_block("wrong fill");
+    }
+    return Ok(pin);
+}
+
+/// PIN encryption keys are single (8-byte), double (16-byte) or triple (24-byte) length.
+fn pin_key(key: &[u8]) -> Result<TripleDes, DesError> {
+    return match key.len() {
+        8 => {
+            let key_block = u64::from_be_bytes(key.try_into().unwrap());
+            Ok(TripleDes::new_ede3(key_block, key_block, key_block))
+        }
+        16 | 24 => TripleDes::from_slice(key),
+        _ => Err(DesError::UnsupportedKeyLength {
+            supported: &[8, 16, 24],
+            actual: key.len(),
+        }),
+    };
+}
+
+pub fn encrypt_pin_block(key: &[u8], pin_block: u64) -> Result<u64, DesError> {
+    return Ok(pin_key(key)?.encrypt_block(pin_block));
+}
+
+pub fn decrypt_pin_block(key: &[u8], encrypted_pin_block: u64) -> Result<u64, DesError> {
+    return Ok(pin_key(key)?.decrypt_block(encrypted_pin_block));
+}
+
+/// PIN translation as an HSM does it: decrypt under the source key, check and re-format
+/// the block, encrypt under the destination key. The PIN never leaves this function.
+pub fn translate_pin_block(
+    encrypted_pin_block: u64,
+    pan: Option<&str>,
+    source_key: &[u8],
+    source_format: PinBlockFormat,
+    destination_key: &[u8],
+    destination_format: PinBlockFormat,
+) -> Result<u64, DesError> {
+    let pin_block = decrypt_pin_block(source_key, encrypted_pin_block)?;
+    let pin = parse_pin_block(source_format, pin_block, pan)?;
+    let pin_block = if source_format == destination_format {
+        pin_block
+    } else {
+        build_pin_block(destination_format, &pin, pan)?
+    };
+    return encrypt_pin_block(destination_key, pin_block);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DesError;
+    use crate::hex::decode;
+    use crate::pin::{
+        build_pin_block, decrypt_pin_block, encrypt_pin_block, parse_pin_block,
+        pin_block_with_fill, translate_pin_block, PinBlockFormat,
+    };
+
+    const PAN: Option<&str> = Some("4111111111111111");
+    const DOUBLE_LENGTH_KEY: &str = "0123456789abcdeffedcba9876543210";
+
+    #[test]
+    fn format_0_pin_block() {
+        let pin_block = build_pin_block(PinBlockFormat::Iso0, "1234", PAN).unwrap();
+        assert_eq!(pin_block, 0x041225eeeeeeeeee);
+        assert_eq!(
+            parse_pin_block(PinBlockFormat::Iso0, pin_block, PAN).unwrap(),
+            "1234"
+        );
+        // short PANs are padded with zeros on the left
+        assert_eq!(
+            build_pin_block(PinBlockFormat::Iso0, "1234", Some("123456")).unwrap(),
+            0x041234fffffedcba
+        );
+    }
+
+    #[test]
+    fn format_1_and_3_fill() {
+        assert_eq!(
+            pin_block_with_fill(PinBlockFormat::Iso1, "1234", None, 0x0123456789abcdef).unwrap(),
+            0x1412346789abcdef
+        );
+        assert_eq!(
+            pin_block_with_fill(PinBlockFormat::Iso3, "1234", PAN, 0xaaaaaaaaaaaaaaaa).unwrap(),
+            0x341225bbbbbbbbbb
+        );
+        for _ in 0..16 {
+            for format in [PinBlockFormat::Iso1, PinBlockFormat::Iso3] {
+                let pin_block = build_pin_block(format, "123456789012", PAN).unwrap();
+                assert_eq!(
+                    parse_pin_block(format, pin_block, PAN).unwrap(),
+                    "123456789012"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parse_rejects_malformed_blocks() {
+        for pin_block in [0x041225eeeeeeeeef, 0x141225eeeeeeeeee, 0x031225eeeeeeeeee] {
+            assert!(matches!(
+                parse_pin_block(PinBlockFormat::Iso0, pin_block, PAN),
+                Err(DesError::InvalidArgument(_))
+            ));
+        }
+        // the same block read against another account
+        assert!(parse_pin_block(
+            PinBlockFormat::Iso0,
+            0x041225eeeeeeeeee,
+            Some("4111111111111129")
+        )
+        .is_err());
+        assert!(matches!(
+            build_pin_block(PinBlockFormat::Iso0, "123", PAN),
+            Err(DesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            build_pin_block(PinBlockFormat::Iso3, "1234", None),
+            Err(DesError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            build_pin_block(PinBlockFormat::Iso0, "12a4", PAN),
+            Err(DesError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn encrypt_pin_block_single_and_double_length() {
+        let single_key = decode("0123456789abcdef").unwrap();
+        let double_key = decode(DOUBLE_LENGTH_KEY).unwrap();
+        assert_eq!(
+            encrypt_pin_block(&single_key, 0x041225eeeeeeeeee).unwrap(),
+            0xc30c31411aa3d043
+        );
+        assert_eq!(
+            encrypt_pin_block(&double_key, 0x041225eeeeeeeeee).unwrap(),
+            0x2a3d408a1977dde9
+        );
+        assert_eq!(
+            decrypt_pin_block(&double_key, 0x2a3d408a1977dde9).unwrap(),
+            0x041225eeeeeeeeee
+        );
+    }
+
+    #[test]
+    fn translate_between_keys_and_formats() {
+        let single_key = decode("0123456789abcdef").unwrap();
+        let double_key = decode(DOUBLE_LENGTH_KEY).unwrap();
+        let translated_pin_block = translate_pin_block(
+            0x2a3d408a1977dde9,
+            PAN,
+            &double_key,
+            PinBlockFormat::Iso0,
+            &single_key,
+            PinBlockFormat::Iso0,
+        )
+        .unwrap();
+        assert_eq!(translated_pin_block, 0xc30c31411aa3d043);
+
+        let translated_pin_block = translate_pin_block(
+            0x2a3d408a1977dde9,
+            PAN,
+            &double_key,
+            PinBlockFormat::Iso0,
+            &single_key,
+            PinBlockFormat::Iso3,
+        )
+        .unwrap();
+        let pin_block = decrypt_pin_block(&single_key, translated_pin_block).unwrap();
+        assert_eq!(
+            parse_pin_block(PinBlockFormat::Iso3, pin_block, PAN).unwrap(),
+            "1234"
+        );
+
+        // decrypting under the wrong source key leaves garbage that does not parse
+        assert!(translate_pin_block(
+            0x2a3d408a1977dde9,
+            PAN,
+            &single_key,
+            PinBlockFormat::Iso0,
+            &double_key,
+            PinBlockFormat::Iso0,
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{error::DesError, parity::fix_parity, weak_keys::classify_key};
+
+pub fn fill_random(bytes: &mut [u8]) -> Result<(), DesError> {
+    return getrandom::getrandom(bytes)
+        .map_err(|error| DesError::RandomUnavailable(error.to_string()));
+}
+
+/// A fresh 64-bit IV from the OS CSPRNG.
+pub fn random_iv() -> Result<u64, DesError> {
+    let mut iv: [u8; 8] = [0; 8];
+    fill_random(&mut iv)?;
+    return Ok(u64::from_be_bytes(iv));
+}
+
+/// A fresh single (8-byte), double (16-byte) or triple (24-byte) length key with odd
+/// parity. Components that are weak, semi-weak or possibly weak, or that repeat an
+/// earlier component and so collapse the EDE to fewer keys, are drawn again.
+pub fn random_key(length: usize) -> Result<Vec<u8>, DesError> {
+    if ![8, 16, 24].contains(&length) {
+        return Err(DesError::UnsupportedKeyLength {
+            supported: &[8, 16, 24],
+            actual: length,
+        });
+    }
+    let mut key: Vec<u8> = Vec::with_capacity(length);
+    while key.len() < length {
+        let mut component: [u8; 8] = [0; 8];
+        fill_random(&mut component)?;
+        let component = fix_parity(&component);
+        let component_block = u64::from_be_bytes(component[..].try_into().unwrap());
+        if classify_key(component_block).is_some() || key.chunks(8).any(|k| k == component) {
+            continue;
+        }
+        key.extend_from_slice(&component);
+    }
+    return Ok(key);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DesError;
+    use crate::parity::check_parity;
+    use crate::random::random_key;
+    use crate::weak_keys::classify_key;
+
+    #[test]
+    fn random_keys_have_odd_parity_and_distinct_strong_components() {
+        for length in [8, 16, 24] {
+            let key = random_key(length).unwrap();
+            assert_eq!(key.len(), length);
+            assert_eq!(check_parity(&key), Ok(()));
+            for component in key.chunks(8) {
+                let component_block = u64::from_be_bytes(component.try_into().unwrap());
+                assert_eq!(classify_key(component_block), None);
+            }
+            if length == 24 {
+                assert_ne!(key[..8], key[16..]);
+            }
+            if length > 8 {
+                assert_ne!(key[..8], key[8..16]);
+            }
+        }
+        assert_ne!(random_key(8).unwrap(), random_key(8).unwrap());
+    }
+
+    #[test]
+    fn random_key_rejects_other_lengths() {
+        assert_eq!(
+            random_key(12),
+            Err(DesError::UnsupportedKeyLength {
+                supported: &[8, 16, 24],
+                actual: 12
+            })
+        );
+    }
+}
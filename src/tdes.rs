@@ -0,0 +1,177 @@
+use crate::{
+    encrypt::{BlockCipher, Des},
+    error::DesError,
+};
+
+/// Triple DES (TDEA) in encrypt-decrypt-encrypt order, each stage with its own cached key
+/// schedule. Keying option 1 uses three independent keys, option 2 reuses the first key as the
+/// third, and option 3 (all three equal) collapses to single DES.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TripleDes {
+    des_1: Des,
+    des_2: Des,
+    des_3: Des,
+}
+
+impl TripleDes {
+    /// EDE3, keying option 1.
+    pub fn new_ede3(key_1: u64, key_2: u64, key_3: u64) -> TripleDes {
+        return TripleDes {
+            des_1: Des::new(key_1),
+            des_2: Des::new(key_2),
+            des_3: Des::new(key_3),
+        };
+    }
+
+    /// EDE2, keying option 2.
+    pub fn new_ede2(key_1: u64, key_2: u64) -> TripleDes {
+        return TripleDes::new_ede3(key_1, key_2, key_1);
+    }
+
+    /// EDE2 from a 16-byte `K1 || K2` key, the double-length keys of the payment standards.
+    pub fn from_double_length(key: &[u8; 16]) -> TripleDes {
+        return TripleDes::new_ede2(
+            u64::from_be_bytes(key[..8].try_into().unwrap()),
+            u64::from_be_bytes(key[8..].try_into().unwrap()),
+        );
+    }
+
+    /// Accepts a 24-byte (EDE3) or 16-byte (EDE2) key. Keying option 3, one key three times,
+    /// is plain DES and has to be asked for with `new_ede3(key, key, key)`.
+    pub fn from_slice(key: &[u8]) -> Result<TripleDes, DesError> {
+        let key_blocks: Vec<u64> = key
+            .chunks(8)
+            .filter(|chunk| chunk.len() == 8)
+            .map(|chunk| {
+                let mut block: [u8; 8] = [0; 8];
+                block.copy_from_slice(chunk);
+                u64::from_be_bytes(block)
+            })
+            .collect();
+        return match (key.len(), key_blocks.as_slice()) {
+            (24, [key_1, key_2, key_3]) => Ok(TripleDes::new_ede3(*key_1, *key_2, *key_3)),
+            (16, [key_1, key_2]) => Ok(TripleDes::new_ede2(*key_1, *key_2)),
+            _ => Err(DesError::UnsupportedKeyLength {
+                supported: &[16, 24],
+                actual: key.len(),
+            }),
+        };
+    }
+}
+
+impl BlockCipher for TripleDes {
+    fn encrypt_block(&self, block: u64) -> u64 {
+        let block = self.des_1.encrypt_block(block);
+        let block = self.des_2.decrypt_block(block);
+        return self.des_3.encrypt_block(block);
+    }
+
+    fn decrypt_block(&self, block: u64) -> u64 {
+        let block = self.des_3.decrypt_block(block);
+        let block = self.des_2.encrypt_block(block);
+        return self.des_1.decrypt_block(block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cbc::{cbc_decrypt, cbc_encrypt};
+    use crate::encrypt::{BlockCipher, Des};
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::tdes::TripleDes;
+
+    #[test]
+    fn tdes_ede3_sp_800_67_example() {
+        let tdes = TripleDes::new_ede3(0x0123456789ABCDEF, 0x23456789ABCDEF01, 0x456789ABCDEF0123);
+        // "The qufck brown fox jump"
+        assert_eq!(tdes.encrypt_block(0x5468652071756663), 0xA826FD8CE53B855F);
+        assert_eq!(tdes.encrypt_block(0x6B2062726F776E20), 0xCCE21C8112256FE6);
+        assert_eq!(tdes.encrypt_block(0x666F78206A756D70), 0x68D5C05DD9B6B900);
+        assert_eq!(tdes.decrypt_block(0x68D5C05DD9B6B900), 0x666F78206A756D70);
+    }
+
+    #[test]
+    fn tdes_sp_800_20_variable_plaintext_kat() {
+        let tdes = TripleDes::from_slice(&[0x01; 24]).unwrap();
+        let expected: [u64; 4] = [
+            0x95F8A5E5DD31D900,
+            0xDD7F121CA5015619,
+            0x2E8653104F3834EA,
+            0x4BD388FF6CD81D4F,
+        ];
+        for (bit, expected_ciphertext) in expected.iter().enumerate() {
+            let plaintext: u64 = 0x8000000000000000 >> bit;
+            assert_eq!(tdes.encrypt_block(plaintext), *expected_ciphertext);
+            assert_eq!(tdes.decrypt_block(*expected_ciphertext), plaintext);
+        }
+    }
+
+    #[test]
+    fn tdes_ede2_matches_16_byte_key() {
+        let tdes =
+            TripleDes::from_slice(&decode("0123456789ABCDEF23456789ABCDEF01").unwrap()).unwrap();
+        assert_eq!(
+            tdes,
+            TripleDes::new_ede2(0x0123456789ABCDEF, 0x23456789ABCDEF01)
+        );
+        assert_eq!(
+            tdes,
+            TripleDes::from_double_length(
+                &decode("0123456789ABCDEF23456789ABCDEF01")
+                    .unwrap()
+                    .try_into()
+                    .unwrap()
+            )
+        );
+        assert_eq!(tdes.encrypt_block(0x5468652071756663), 0xc44862f70cf2fbdc);
+    }
+
+    #[test]
+    fn tdes_keying_option_3_is_single_des() {
+        let tdes = TripleDes::new_ede3(0x133457799BBCDFF1, 0x133457799BBCDFF1, 0x133457799BBCDFF1);
+        let des = Des::new(0x133457799BBCDFF1);
+        assert_eq!(
+            tdes.encrypt_block(0x0123456789ABCDEF),
+            des.encrypt_block(0x0123456789ABCDEF)
+        );
+    }
+
+    #[test]
+    fn tdes_cbc_round_trip() {
+        let key = decode("0123456789ABCDEF23456789ABCDEF01456789ABCDEF0123").unwrap();
+        let tdes = TripleDes::from_slice(&key).unwrap();
+        let plaintext = b"Now is the time for all ";
+        let ciphertext = cbc_encrypt(&tdes, 0x1234567890abcdef, plaintext).unwrap();
+        assert_eq!(
+            encode(&ciphertext),
+            "f3c0ff026c023089656fbb169def7edb30ba36075d6f0176"
+        );
+        assert_eq!(
+            cbc_decrypt(&tdes, 0x1234567890abcdef, &ciphertext).unwrap(),
+            plaintext
+        );
+    }
+
+    #[test]
+    fn tdes_rejects_other_key_lengths() {
+        assert_eq!(
+            TripleDes::from_slice(&[0; 20]),
+            Err(DesError::UnsupportedKeyLength {
+                supported: &[16, 24],
+                actual: 20
+            })
+        );
+        for length in [8, 17] {
+            assert_eq!(
+                TripleDes::from_slice(&vec![0; length])
+                    .unwrap_err()
+                    .to_string(),
+                format!(
+                    "invalid key length: expected 16 or 24 bytes, got {}",
+                    length
+                )
+            );
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{ct::constant_time_eq, encrypt::Des, error::DesError};
+
+/// Key the VNC servers and viewers obfuscate stored passwords with, as written in d3des
+/// bit order. It goes through the same bit reversal as a password key.
+pub const VNC_FIXED_KEY: [u8; 8] = [23, 82, 107, 6, 35, 78, 88, 7];
+pub const VNC_CHALLENGE_LENGTH: usize = 16;
+
+/// The DES key for a VNC password: the first 8 bytes, zero-padded, with the bits of every
+/// byte reversed. The reference implementation's d3des numbers key bits from the least
+/// significant end, so the reversal is part of the protocol.
+pub fn vnc_key(password: &[u8]) -> Des {
+    let mut key: [u8; 8] = [0; 8];
+    for (key_byte, password_byte) in key.iter_mut().zip(password) {
+        *key_byte = password_byte.reverse_bits();
+    }
+    return Des::from_bytes(key);
+}
+
+/// RFB VNC authentication: both 8-byte halves of the challenge encrypted in ECB.
+pub fn vnc_response(
+    password: &[u8],
+    challenge: &[u8; VNC_CHALLENGE_LENGTH],
+) -> [u8; VNC_CHALLENGE_LENGTH] {
+    let des = vnc_key(password);
+    let mut response: [u8; VNC_CHALLENGE_LENGTH] = [0; VNC_CHALLENGE_LENGTH];
+    for (challenge_block, response_block) in
+        challenge.chunks_exact(8).zip(response.chunks_exact_mut(8))
+    {
+        let block = u64::from_be_bytes(challenge_block.try_into().unwrap());
+        response_block.copy_from_slice(&des.encrypt_block(block).to_be_bytes());
+    }
+    return response;
+}
+
+/// Server side check of the client's 16-byte response in constant time.
+pub fn verify_vnc_response(
+    password: &[u8],
+    challenge: &[u8; VNC_CHALLENGE_LENGTH],
+    response: &[u8],
+) -> Result<bool, DesError> {
+    if response.len() != VNC_CHALLENGE_LENGTH {
+        return Err(DesError::InvalidBlockLength {
+            expected: VNC_CHALLENGE_LENGTH,
+            actual: response.len(),
+        });
+    }
+    return Ok(constant_time_eq(
+        &vnc_response(password, challenge),
+        response,
+    ));
+}
+
+/// The 8 bytes a VNC `passwd` file stores: the password, truncated or zero-padded to 8
+/// bytes, encrypted under the fixed key.
+pub fn encode_vnc_password(password: &[u8]) -> [u8; 8] {
+    let mut password_block: [u8; 8] = [0; 8];
+    let length = password.len().min(8);
+    password_block[..length].copy_from_slice(&password[..length]);
+    let block = vnc_key(&VNC_FIXED_KEY).encrypt_block(u64::from_be_bytes(password_block));
+    return block.to_be_bytes();
+}
+
+/// Recovers the password from a stored VNC password file. Only the first 8 bytes are the
+/// password; some servers append a second block for the view-only password.
+pub fn decode_vnc_password(stored_password: &[u8]) -> Result<Vec<u8>, DesError> {
+    if stored_password.len() < 8 {
+        return Err(DesError::InvalidBlockLength {
+            expected: 8,
+            actual: stored_password.len(),
+        });
+    }
+    let block = u64::from_be_bytes(stored_password[..8].try_into().unwrap());
+    let mut password = vnc_key(&VNC_FIXED_KEY)
+        .decrypt_block(block)
+        .to_be_bytes()
+        .to_vec();
+    while password.last() == Some(&0) {
+        password.pop();
+    }
+    return Ok(password);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DesError;
+    use crate::hex::{decode, encode};
+    use crate::vnc::{
+        decode_vnc_password, encode_vnc_password, verify_vnc_response, vnc_key, vnc_response,
+        VNC_FIXED_KEY,
+    };
+    use crate::Des;
+
+    #[test]
+    fn fixed_key_reversed_is_standard_des_key() {
+        assert_eq!(
+            vnc_key(&VNC_FIXED_KEY).subkeys(),
+            Des::new(0xe84ad660c4721ae0).subkeys()
+        );
+    }
+
+    #[test]
+    fn vnc_password_file_round_trip() {
+        assert_eq!(
+            encode(&encode_vnc_password(b"password")),
+            "dbd83cfd727a1458"
+        );
+        assert_eq!(encode(&encode_vnc_password(b"secret")), "2e2dbf576eb06c9e");
+        assert_eq!(
+            decode_vnc_password(&decode("dbd83cfd727a1458").unwrap()).unwrap(),
+            b"password"
+        );
+        assert_eq!(
+            decode_vnc_password(&decode("2e2dbf576eb06c9e").unwrap()).unwrap(),
+            b"secret"
+        );
+        // only 8 characters are stored
+        assert_eq!(
+            decode_vnc_password(&encode_vnc_password(b"password123")).unwrap(),
+            b"password"
+        );
+        assert_eq!(
+            decode_vnc_password(&[0; 4]),
+            Err(DesError::InvalidBlockLength {
+                expected: 8,
+                actual: 4
+            })
+        );
+    }
+
+    #[test]
+    fn vnc_response_to_challenge() {
+        let challenge: [u8; 16] = decode("6b6c6d6e6f707172737475767778797a")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let response = vnc_response(b"secret", &challenge);
+        assert_eq!(encode(&response), "08c4708600466be6774a8a2c945af6db");
+        assert_eq!(
+            verify_vnc_response(b"secret", &challenge, &response),
+            Ok(true)
+        );
+        assert_eq!(
+            verify_vnc_response(b"Secret", &challenge, &response),
+            Ok(false)
+        );
+        // passwords are cut to 8 bytes
+        assert_eq!(
+            vnc_response(b"secret\0\0ignored", &challenge),
+            vnc_response(b"secret", &challenge)
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use std::fmt;
+
+use crate::{
+    encrypt::{get_permutated_block, split_permutated_key_56},
+    permutation_tables::PC_1_TABLE,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WeakKeyClass {
+    /// One of the 4 keys whose 16 subkeys are all equal; encryption is an involution.
+    Weak,
+    /// One of the 12 keys with only 2 distinct subkeys; each has a partner that decrypts it.
+    SemiWeak,
+    /// One of the 48 keys with only 4 distinct subkeys.
+    PossiblyWeak,
+}
+
+impl fmt::Display for WeakKeyClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            WeakKeyClass::Weak => write!(f, "weak"),
+            WeakKeyClass::SemiWeak => write!(f, "semi-weak"),
+            WeakKeyClass::PossiblyWeak => write!(f, "possibly weak"),
+        };
+    }
+}
+
+// 28-bit C/D register contents that repeat every 1 or 2 bits
+const CONSTANT_HALVES: [u64; 2] = [0x0000000, 0xFFFFFFF];
+const ALTERNATING_HALVES: [u64; 2] = [0x5555555, 0xAAAAAAA];
+// 28-bit register contents that repeat the pattern 0011 (and its rotations) every 4 bits
+const PAIRED_HALVES: [u64; 4] = [0x3333333, 0x6666666, 0xCCCCCCC, 0x9999999];
+
+/// Classifies a key by its PC-1 output, so the parity bits make no difference. The
+/// subkeys only repeat when both 28-bit halves are periodic under the rotation schedule.
+pub fn classify_key(key_block: u64) -> Option<WeakKeyClass> {
+    let permutated_key_block = get_permutated_block(key_block, PC_1_TABLE, 0);
+    let (left, right) = split_permutated_key_56(permutated_key_block);
+    let is_constant = |half: u64| CONSTANT_HALVES.contains(&half);
+    let is_alternating = |half: u64| is_constant(half) || ALTERNATING_HALVES.contains(&half);
+    let is_paired = |half: u64| is_alternating(half) || PAIRED_HALVES.contains(&half);
+
+    if is_constant(left) && is_constant(right) {
+        return Some(WeakKeyClass::Weak);
+    }
+    if is_alternating(left) && is_alternating(right) {
+        return Some(WeakKeyClass::SemiWeak);
+    }
+    if is_paired(left) && is_paired(right) {
+        return Some(WeakKeyClass::PossiblyWeak);
+    }
+    return None;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::encrypt::Des;
+    use crate::permutation_tables::PC_1_TABLE;
+    use crate::weak_keys::{classify_key, WeakKeyClass};
+
+    #[test]
+    fn the_four_weak_keys() {
+        for key in [
+            0x0101010101010101,
+            0xFEFEFEFEFEFEFEFE,
+            0xE0E0E0E0F1F1F1F1,
+            0x1F1F1F1F0E0E0E0E,
+        ] {
+            assert_eq!(classify_key(key), Some(WeakKeyClass::Weak), "{:016x}", key);
+        }
+        // parity bits are ignored
+        assert_eq!(classify_key(0x0000000000000000), Some(WeakKeyClass::Weak));
+    }
+
+    #[test]
+    fn semi_weak_keys_come_in_decrypting_pairs() {
+        let pairs: [(u64, u64); 6] = [
+            (0x011F011F010E010E, 0x1F011F010E010E01),
+            (0x01E001E001F101F1, 0xE001E001F101F101),
+            (0x01FE01FE01FE01FE, 0xFE01FE01FE01FE01),
+            (0x1FE01FE00EF10EF1, 0xE01FE01FF10EF10E),
+            (0x1FFE1FFE0EFE0EFE, 0xFE1FFE1FFE0EFE0E),
+            (0xE0FEE0FEF1FEF1FE, 0xFEE0FEE0FEF1FEF1),
+        ];
+        for (key, partner) in pairs {
+            assert_eq!(classify_key(key), Some(WeakKeyClass::SemiWeak));
+            assert_eq!(classify_key(partner), Some(WeakKeyClass::SemiWeak));
+            let ciphertext = Des::new(key).encrypt_block(0x0123456789ABCDEF);
+            assert_eq!(
+                Des::new(partner).encrypt_block(ciphertext),
+                0x0123456789ABCDEF
+            );
+        }
+    }
+
+    #[test]
+    fn possibly_weak_keys() {
+        for key in [0x1F1F01010E0E0101, 0xE0FE1F01F1FE0E01, 0xFEFE1F1FFEFE0E0E] {
+            assert_eq!(
+                classify_key(key),
+                Some(WeakKeyClass::PossiblyWeak),
+                "{:016x}",
+                key
+            );
+            let mut subkeys = Des::new(key).subkeys().to_vec();
+            subkeys.sort();
+            subkeys.dedup();
+            assert_eq!(subkeys.len(), 4);
+        }
+    }
+
+    #[test]
+    fn ordinary_keys_are_not_weak() {
+        assert_eq!(classify_key(0x133457799BBCDFF1), None);
+        assert_eq!(classify_key(0x0123456789ABCDEF), None);
+    }
+
+    #[test]
+    fn weak_key_class_counts() {
+        // walk every combination of periodic C and D halves back through PC-1
+        let halves: [u64; 8] = [
+            0x0000000, 0xFFFFFFF, 0x5555555, 0xAAAAAAA, 0x3333333, 0x6666666, 0xCCCCCCC, 0x9999999,
+        ];
+        let mut counts = [0; 3];
+        for left in halves {
+            for right in halves {
+                let key = key_from_pc1_halves(left, right);
+                match classify_key(key) {
+                    Some(WeakKeyClass::Weak) => counts[0] += 1,
+                    Some(WeakKeyClass::SemiWeak) => counts[1] += 1,
+                    Some(WeakKeyClass::PossiblyWeak) => counts[2] += 1,
+                    None => panic!("{:016x} not classified", key),
+                }
+            }
+        }
+        assert_eq!(counts, [4, 12, 48]);
+    }
+
+    fn key_from_pc1_halves(left: u64, right: u64) -> u64 {
+        // invert PC-1 by scattering the 56 bits back to their key positions
+        let permutated_key_block = (left << 28) | right;
+        let mut key_block: u64 = 0;
+        for (index, position) in PC_1_TABLE.iter().enumerate() {
+            let bit = (permutated_key_block >> (55 - index)) & 1;
+            key_block |= bit << (64 - *position as u64);
+        }
+        return key_block;
+    }
+}